@@ -0,0 +1,54 @@
+use macroquad::prelude::*;
+use crate::achievements::{Achievements, ACHIEVEMENTS_PATH, ALL_ACHIEVEMENTS};
+
+//成就界面：列出所有成就，解锁过的显示解锁的日期
+pub struct AchievementScreen {
+    achievements: Achievements,
+}
+
+impl AchievementScreen {
+    //每次打开时重新读取成就文件
+    pub fn load() -> Self {
+        Self {
+            achievements: Achievements::load_or_recover(ACHIEVEMENTS_PATH),
+        }
+    }
+
+    //返回 true 表示回到菜单
+    pub fn update(&mut self) -> bool {
+        is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::A)
+    }
+
+    //绘制成就列表（背景由菜单负责绘制）
+    pub fn draw(&self) {
+        draw_rectangle(0.0, 0.0, screen_width(), screen_height(), Color::new(0.0, 0.0, 0.0, 0.7));
+
+        let title = format!("Achievements  {}/{}", self.achievements.count(), ALL_ACHIEVEMENTS.len());
+        let title_size = 48.0;
+        let title_width = measure_text(&title, None, title_size as u16, 1.0).width;
+        draw_text(&title, screen_width() / 2.0 - title_width / 2.0 + 2.0, 90.0 + 2.0, title_size, BLACK);
+        draw_text(&title, screen_width() / 2.0 - title_width / 2.0, 90.0, title_size, YELLOW);
+
+        //每个成就占两行：名字（和解锁日期）、说明；没解锁的画成灰色
+        for (i, &achievement) in ALL_ACHIEVEMENTS.iter().enumerate() {
+            let y = 140.0 + i as f32 * 54.0;
+            let (title, color) = match self.achievements.unlocked(achievement) {
+                Some(date) => (format!("{}  ({})", achievement.title(), date), GOLD),
+                None => (achievement.title().to_string(), GRAY),
+            };
+            draw_text(&title, 60.0, y, 26.0, color);
+            draw_text(achievement.description(), 80.0, y + 22.0, 20.0, if color == GRAY { GRAY } else { WHITE });
+        }
+
+        let hint = "ESC or A: back";
+        let hint_size = 20.0;
+        let hint_width = measure_text(hint, None, hint_size as u16, 1.0).width;
+        draw_text(
+            hint,
+            screen_width() / 2.0 - hint_width / 2.0,
+            screen_height() - 30.0,
+            hint_size,
+            Color::new(0.4, 0.8, 1.0, 1.0),
+        );
+    }
+}
@@ -0,0 +1,158 @@
+//成就模块：根据一局进行中的统计信息（存活时间、血包、被攻击次数、击杀、首领、波次）解锁成就
+//解锁的成就和解锁的日期保存在 data/achievements.json 中
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::date::Date;
+use crate::storage::{load_json_or_recover, save_json};
+use crate::game::RunStats;
+use crate::menu::GameDifficulty;
+
+//成就文件
+pub const ACHIEVEMENTS_PATH: &str = "data/achievements.json";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Achievement {
+    //第一次打死怪物
+    FirstBlood,
+    //一局吃到 10 个血包
+    PackRat,
+    //开局之后 60 秒内一次都没有被攻击
+    Untouchable,
+    //在困难难度下存活 120 秒
+    HardSurvivor,
+    //一局打死 25 个怪物
+    Slayer,
+    //到达第 5 波
+    WaveRider,
+    //打败首领
+    BossSlayer,
+    //任意难度下存活 300 秒
+    Marathon,
+}
+
+//所有的成就（也是成就界面中的顺序）
+pub const ALL_ACHIEVEMENTS: [Achievement; 8] = [
+    Achievement::FirstBlood,
+    Achievement::PackRat,
+    Achievement::Untouchable,
+    Achievement::HardSurvivor,
+    Achievement::Slayer,
+    Achievement::WaveRider,
+    Achievement::BossSlayer,
+    Achievement::Marathon,
+];
+
+impl Achievement {
+    pub fn title(self) -> &'static str {
+        match self {
+            Achievement::FirstBlood => "First Blood",
+            Achievement::PackRat => "Pack Rat",
+            Achievement::Untouchable => "Untouchable",
+            Achievement::HardSurvivor => "Hard Survivor",
+            Achievement::Slayer => "Slayer",
+            Achievement::WaveRider => "Wave Rider",
+            Achievement::BossSlayer => "Boss Slayer",
+            Achievement::Marathon => "Marathon",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Achievement::FirstBlood => "Slay your first monster",
+            Achievement::PackRat => "Collect 10 health packs in one run",
+            Achievement::Untouchable => "Survive 60 s without being attacked",
+            Achievement::HardSurvivor => "Survive 120 s on Hard",
+            Achievement::Slayer => "Slay 25 monsters in one run",
+            Achievement::WaveRider => "Reach wave 5",
+            Achievement::BossSlayer => "Defeat the boss",
+            Achievement::Marathon => "Survive 300 s in one run",
+        }
+    }
+
+    //这一局到目前为止是否达成了这个成就
+    fn is_met(self, stats: &RunStats) -> bool {
+        match self {
+            Achievement::FirstBlood => stats.kills >= 1,
+            Achievement::PackRat => stats.health_packs_collected >= 10,
+            Achievement::Untouchable => stats.game_time >= 60.0 && stats.monster_attacks == 0,
+            Achievement::HardSurvivor => stats.difficulty == GameDifficulty::Hard && stats.game_time >= 120.0,
+            Achievement::Slayer => stats.kills >= 25,
+            Achievement::WaveRider => stats.best_wave >= 5,
+            Achievement::BossSlayer => stats.boss_defeated,
+            Achievement::Marathon => stats.game_time >= 300.0,
+        }
+    }
+}
+
+//解锁过的成就
+#[derive(Default, Serialize, Deserialize)]
+pub struct Achievements {
+    //每个解锁过的成就和解锁的日期
+    #[serde(default)]
+    unlocked: HashMap<Achievement, Date>,
+}
+
+impl Achievements {
+    //读取解锁过的成就，文件不存在或者损坏时返回空的记录
+    pub fn load_or_recover(path: &str) -> Self {
+        load_json_or_recover(path, "成就文件")
+    }
+
+    //把解锁过的成就保存到文件
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        save_json(path, self, "成就文件")
+    }
+
+    //某个成就解锁的日期，还没有解锁时返回 None
+    pub fn unlocked(&self, achievement: Achievement) -> Option<Date> {
+        self.unlocked.get(&achievement).copied()
+    }
+
+    //解锁过的成就数量
+    pub fn count(&self) -> usize {
+        self.unlocked.len()
+    }
+
+    //用这一局的统计信息检查所有还没解锁的成就，返回这次新解锁的成就
+    pub fn update(&mut self, stats: &RunStats, today: Date) -> Vec<Achievement> {
+        let new: Vec<Achievement> = ALL_ACHIEVEMENTS
+            .into_iter()
+            .filter(|a| !self.unlocked.contains_key(a) && a.is_met(stats))
+            .collect();
+        for &achievement in &new {
+            self.unlocked.insert(achievement, today);
+        }
+        new
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scoring::ScoreBreakdown;
+
+    #[test]
+    fn achievements_unlock_once() {
+        let mut stats = RunStats {
+            seed: 1,
+            game_time: 0.0,
+            health_packs_collected: 0,
+            monster_attacks: 0,
+            kills: 0,
+            boss_defeated: false,
+            best_wave: 1,
+            difficulty: GameDifficulty::Hard,
+            score: ScoreBreakdown::default(),
+        };
+        let mut achievements = Achievements::default();
+        assert!(achievements.update(&stats, Date::from_days(0)).is_empty());
+
+        stats.game_time = 130.0;
+        stats.kills = 1;
+        let new = achievements.update(&stats, Date::from_days(0));
+        assert_eq!(new, vec![Achievement::FirstBlood, Achievement::Untouchable, Achievement::HardSurvivor]);
+        //已经解锁的成就不会再解锁一次
+        assert!(achievements.update(&stats, Date::from_days(1)).is_empty());
+        assert_eq!(achievements.unlocked(Achievement::FirstBlood), Some(Date::from_days(0)));
+    }
+}
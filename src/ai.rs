@@ -0,0 +1,70 @@
+//怪物行为模块：每个怪物都有自己的状态，在 游荡 / 追赶 / 搜索 / 逃跑 之间切换
+//  游荡：随机乱走，直到在视野半径内发现玩家
+//  追赶：玩家可见时沿最短路径追赶玩家
+//  搜索：看不见玩家之后，前往最后一次看见玩家的位置，搜索一段时间后放弃
+//  逃跑：玩家处于强化状态时远离玩家
+//状态切换用到的参数都放在 Behaviour 中，每种怪物在定义文件的 [monsters.种类] 里写自己的参数（例如首领从不逃跑）
+use serde::{Deserialize, Serialize};
+
+//怪物的行为参数
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Behaviour {
+    //视野半径（格子数），玩家在这个范围内才会被发现
+    pub sight_radius: f32,
+    //丢失玩家之后搜索的时间（秒）
+    pub search_time: f32,
+    //玩家处于强化状态时是否逃跑
+    pub flees: bool,
+}
+
+//怪物当前的行为状态
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AiState {
+    Wander,
+    //last_seen 为最后一次看见玩家时玩家所在的格子
+    Chase { last_seen: (usize, usize) },
+    Search { target: (usize, usize), time_left: f32 },
+    Flee,
+}
+
+//怪物这一步对玩家的感知
+pub struct Perception {
+    //玩家所在的格子
+    pub player: (usize, usize),
+    //怪物能否看见玩家
+    pub player_visible: bool,
+    //玩家是否处于强化状态
+    pub player_powered_up: bool,
+}
+
+impl AiState {
+    //根据感知结果切换到下一个状态，elapsed 为距离上一次决策经过的时间
+    pub fn next(self, behaviour: &Behaviour, perception: &Perception, position: (usize, usize), elapsed: f32) -> AiState {
+        //玩家处于强化状态时，看得见玩家的怪物会逃跑（已经在逃跑的怪物会一直逃到强化结束）
+        if behaviour.flees && perception.player_powered_up && (perception.player_visible || self == AiState::Flee) {
+            return AiState::Flee;
+        }
+        if perception.player_visible {
+            return AiState::Chase { last_seen: perception.player };
+        }
+
+        match self {
+            //刚刚看不见玩家：去玩家最后出现的位置搜索
+            AiState::Chase { last_seen } => AiState::Search {
+                target: last_seen,
+                time_left: behaviour.search_time,
+            },
+            AiState::Search { target, time_left } => {
+                let time_left = time_left - elapsed;
+                //到达目标位置或者搜索时间用完，就放弃搜索
+                if position == target || time_left <= 0.0 {
+                    AiState::Wander
+                } else {
+                    AiState::Search { target, time_left }
+                }
+            }
+            AiState::Wander | AiState::Flee => AiState::Wander,
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//首领模块：游戏进行一段时间之后出现一个首领，生命值每降到一个阈值就进入下一个阶段，换一种攻击方式
+//首领本身和普通怪物一样放在怪物列表里（一样会移动、撞人、被打、被击退），这里只负责出场和各个阶段的攻击
+use ::rand::Rng;
+use crate::rng::GameRng;
+use crate::definitions::{BossPattern, Definitions};
+use crate::level::Level;
+use crate::map::{Map, DIRECTIONS_8};
+use crate::monster::Monsters;
+use crate::monster_kind::MonsterKind;
+use crate::projectile::{Projectile, Projectiles};
+use serde::{Deserialize, Serialize};
+
+//首领出场的进度
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum BossState {
+    //还没有出现
+    Waiting,
+    //正在战斗，phase 为已经进入的阶段数（0 表示还在初始阶段），timer 为距离下一次发动攻击的时间
+    Fighting { phase: usize, timer: f32 },
+    //已经被打败
+    Defeated,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct BossEncounter {
+    pub state: BossState,
+    //首领出场时可以选择的格子（关卡的怪物出生点，没有时为所有地板）
+    spawn_tiles: Vec<(usize, usize)>,
+}
+
+impl BossEncounter {
+    pub fn new(level: &Level) -> Self {
+        let spawn_tiles = if level.monster_spawns.is_empty() {
+            level.map.floor_tiles()
+        } else {
+            level.monster_spawns.clone()
+        };
+        Self {
+            state: BossState::Waiting,
+            spawn_tiles,
+        }
+    }
+
+    //出场的格子是否都在这张地图的地板上（至少要有一个）
+    pub fn fits(&self, map: &Map) -> bool {
+        !self.spawn_tiles.is_empty() && self.spawn_tiles.iter().all(|&(x, y)| map.is_floor(x, y))
+    }
+
+    //首领是否已经被打败
+    pub fn is_defeated(&self) -> bool {
+        self.state == BossState::Defeated
+    }
+
+    //首领被玩家打死了
+    pub fn defeat(&mut self) {
+        self.state = BossState::Defeated;
+    }
+
+    //首领是否应该出场了
+    pub fn should_appear(&self, game_time: f32, definitions: &Definitions) -> bool {
+        self.state == BossState::Waiting && game_time >= definitions.boss.spawn_time
+    }
+
+    //战斗中根据首领的生命值切换阶段，并按阶段的间隔发动攻击
+    //召唤的怪物直接加进 monsters，射出的弹丸放进 projectiles；进入新的阶段时返回阶段的序号（从 1 开始）
+    pub fn update(
+        &mut self,
+        delta: f32,
+        definitions: &Definitions,
+        map: &Map,
+        monsters: &mut Monsters,
+        projectiles: &mut Projectiles,
+        player: (usize, usize),
+    ) -> Option<usize> {
+        let BossState::Fighting { mut phase, mut timer } = self.state else {
+            return None;
+        };
+        let boss = monsters.list.iter().find(|m| m.kind == MonsterKind::Boss)?;
+        let position = boss.tile();
+        let health_ratio = boss.health as f32 / boss.stats.health as f32;
+        let phases = &definitions.boss.phases;
+
+        //生命值降到下一个阶段的阈值以下：进入下一个阶段，并立刻发动一次攻击
+        let mut entered = None;
+        while phase < phases.len() && health_ratio <= phases[phase].health_below {
+            phase += 1;
+            timer = 0.0;
+            entered = Some(phase);
+        }
+
+        if phase > 0 {
+            timer -= delta;
+            if timer <= 0.0 {
+                let current = &phases[phase - 1];
+                timer = current.interval;
+                match current.pattern {
+                    BossPattern::Summon { kind, count } => {
+                        summon(definitions, map, monsters, position, player, kind, count);
+                    }
+                    BossPattern::Burst { damage, speed, range } => {
+                        for direction in DIRECTIONS_8 {
+                            projectiles.spawn(Projectile::new(position.0, position.1, direction, speed, damage, range));
+                        }
+                    }
+                }
+            }
+        }
+        self.state = BossState::Fighting { phase, timer };
+        entered
+    }
+
+    //首领出场：在离玩家足够远、没有被占着的格子上生成首领（优先选离玩家最远的几个格子之一）
+    //没有合适的格子时首领这一步不出场，仍然是 Waiting，下一步再试；出场了返回 true
+    pub fn appear(&mut self, definitions: &Definitions, monsters: &mut Monsters, player: (usize, usize), rng: &mut GameRng) -> bool {
+        let distance = |(x, y): (usize, usize)| x.abs_diff(player.0).pow(2) + y.abs_diff(player.1).pow(2);
+        let mut candidates: Vec<(usize, usize)> = self
+            .spawn_tiles
+            .iter()
+            .copied()
+            .filter(|&(x, y)| !monsters.is_occupied(x, y) && distance((x, y)) > 2)
+            .collect();
+        if candidates.is_empty() {
+            return false;
+        }
+        candidates.sort_by_key(|&tile| std::cmp::Reverse(distance(tile)));
+        candidates.truncate(3);
+        let position = candidates[rng.gen_range(0..candidates.len())];
+
+        monsters.spawn(MonsterKind::Boss, definitions.monster(MonsterKind::Boss).clone(), position);
+        self.state = BossState::Fighting { phase: 0, timer: 0.0 };
+        true
+    }
+}
+
+//在首领身边的空地上召唤最多 count 个怪物（不会召唤在玩家脚下）
+fn summon(
+    definitions: &Definitions,
+    map: &Map,
+    monsters: &mut Monsters,
+    around: (usize, usize),
+    player: (usize, usize),
+    kind: MonsterKind,
+    count: usize,
+) {
+    let mut summoned = 0;
+    for (x, y) in map.neighbours(around.0, around.1, true).collect::<Vec<_>>() {
+        if summoned == count {
+            break;
+        }
+        if map.is_floor(x, y) && (x, y) != player && !monsters.is_occupied(x, y) {
+            monsters.spawn(kind, definitions.monster(kind).clone(), (x, y));
+            summoned += 1;
+        }
+    }
+}
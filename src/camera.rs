@@ -0,0 +1,66 @@
+use macroquad::prelude::*;
+use crate::map::Map;
+use crate::TILE_SIZE;
+
+//镜头跟随的平滑程度，数值越大镜头追上玩家越快
+const FOLLOW_SPEED: f32 = 8.0;
+
+//镜头结构体
+//作用：地图比窗口大时，让画面跟随玩家滚动；只影响地图、玩家、怪物和血包的绘制，HUD 仍然画在屏幕坐标上
+pub struct Camera {
+    //镜头左上角在地图上的像素坐标
+    position: Vec2,
+}
+
+impl Camera {
+    //创建镜头并直接对准玩家（不做平滑过渡）
+    pub fn new(target: Vec2, map: &Map) -> Self {
+        let mut camera = Self { position: Vec2::ZERO };
+        camera.position = camera.clamped(target, map);
+        camera
+    }
+
+    //让镜头平滑地跟随目标（目标为玩家中心的像素坐标），delta 为这一帧的时间
+    pub fn update(&mut self, target: Vec2, map: &Map, delta: f32) {
+        let goal = self.clamped(target, map);
+        let t = 1.0 - (-FOLLOW_SPEED * delta).exp();
+        self.position += (goal - self.position) * t;
+    }
+
+    //以 target 为中心时镜头的位置，并限制在地图范围内；地图比窗口小时让地图居中
+    fn clamped(&self, target: Vec2, map: &Map) -> Vec2 {
+        let screen = vec2(screen_width(), screen_height());
+        let map_size = vec2(map.width() as f32, map.height() as f32) * TILE_SIZE;
+        let clamp_axis = |center: f32, screen: f32, map: f32| {
+            if map <= screen {
+                (map - screen) / 2.0
+            } else {
+                (center - screen / 2.0).clamp(0.0, map - screen)
+            }
+        };
+        vec2(
+            clamp_axis(target.x, screen.x, map_size.x),
+            clamp_axis(target.y, screen.y, map_size.y),
+        )
+    }
+
+    //地图上的像素坐标转换成屏幕坐标
+    pub fn to_screen(&self, world: Vec2) -> Vec2 {
+        world - self.position
+    }
+
+    //以 (x, y) 为左上角、一个格子大小的物体是否出现在屏幕上（屏幕外的物体不用绘制）
+    pub fn is_visible(&self, x: f32, y: f32) -> bool {
+        let pos = self.to_screen(vec2(x, y) * TILE_SIZE);
+        pos.x > -TILE_SIZE && pos.y > -TILE_SIZE && pos.x < screen_width() && pos.y < screen_height()
+    }
+
+    //屏幕上能看到的格子范围（左闭右开），用来裁剪地图的绘制
+    pub fn visible_tiles(&self, map: &Map) -> (std::ops::Range<usize>, std::ops::Range<usize>) {
+        let first_x = (self.position.x / TILE_SIZE).floor().max(0.0) as usize;
+        let first_y = (self.position.y / TILE_SIZE).floor().max(0.0) as usize;
+        let last_x = (((self.position.x + screen_width()) / TILE_SIZE).ceil().max(0.0) as usize).min(map.width());
+        let last_y = (((self.position.y + screen_height()) / TILE_SIZE).ceil().max(0.0) as usize).min(map.height());
+        (first_x..last_x, first_y..last_y)
+    }
+}
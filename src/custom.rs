@@ -0,0 +1,231 @@
+//自定义难度模块：玩家在菜单里自己设定怪物数量、速度、伤害、血包等数值，用来练习特定的场景
+//自定义的数值覆盖在定义文件之上，得到这一局使用的数值定义（录像里保存的也是覆盖之后的数值，所以录像照样能播放）
+//设定好的数值可以起个名字保存成预设，所有预设都存放在 data/custom_presets.json 中
+use serde::{Deserialize, Serialize};
+use crate::storage::{load_json_or_recover, save_json};
+use crate::definitions::Definitions;
+use crate::menu::GameDifficulty;
+use crate::monster_kind::MonsterKind;
+
+//保存预设的文件
+pub const PRESETS_PATH: &str = "data/custom_presets.json";
+//预设名字的最大长度
+pub const MAX_NAME_LEN: usize = 20;
+
+//一套自定义的数值
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CustomDifficulty {
+    //预设的名字
+    pub name: String,
+    //开局时的怪物数量（按难度预设 Custom 里的种类轮流生成）
+    pub monster_count: usize,
+    //怪物速度的倍率（1.0 为定义文件中的速度），移动间隔除以这个倍率
+    pub monster_speed: f32,
+    //怪物碰到玩家时造成的伤害（所有种类都一样）
+    pub monster_damage: i32,
+    //被怪物攻击之后的无敌时间（秒）
+    pub damage_cooldown: f32,
+    //吃到血包回复的生命值
+    pub heal: i32,
+    //刷新血包的间隔（秒）
+    pub pack_spawn_interval: f32,
+    //开局时的生命值（也是最大生命值）
+    pub starting_health: i32,
+}
+
+//自定义难度中可以调整的数值
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CustomField {
+    MonsterCount,
+    MonsterSpeed,
+    MonsterDamage,
+    DamageCooldown,
+    Heal,
+    PackSpawnInterval,
+    StartingHealth,
+}
+
+//所有可以调整的数值（也是编辑界面中的顺序）
+pub const ALL_FIELDS: [CustomField; 7] = [
+    CustomField::MonsterCount,
+    CustomField::MonsterSpeed,
+    CustomField::MonsterDamage,
+    CustomField::DamageCooldown,
+    CustomField::Heal,
+    CustomField::PackSpawnInterval,
+    CustomField::StartingHealth,
+];
+
+impl CustomField {
+    //编辑界面中显示的名字
+    pub fn label(self) -> &'static str {
+        match self {
+            CustomField::MonsterCount => "Monsters",
+            CustomField::MonsterSpeed => "Monster speed",
+            CustomField::MonsterDamage => "Damage per hit",
+            CustomField::DamageCooldown => "Damage cooldown",
+            CustomField::Heal => "Heal amount",
+            CustomField::PackSpawnInterval => "Pack spawn interval",
+            CustomField::StartingHealth => "Starting HP",
+        }
+    }
+
+    //数值的范围和每次调整的步长：(最小值, 最大值, 步长)
+    fn range(self) -> (f32, f32, f32) {
+        match self {
+            CustomField::MonsterCount => (1.0, 20.0, 1.0),
+            CustomField::MonsterSpeed => (0.25, 3.0, 0.25),
+            CustomField::MonsterDamage => (0.0, 100.0, 5.0),
+            CustomField::DamageCooldown => (0.0, 5.0, 0.25),
+            CustomField::Heal => (5.0, 100.0, 5.0),
+            CustomField::PackSpawnInterval => (1.0, 60.0, 1.0),
+            CustomField::StartingHealth => (10.0, 500.0, 10.0),
+        }
+    }
+}
+
+impl CustomDifficulty {
+    //以定义文件中的数值为起点
+    pub fn new(definitions: &Definitions) -> Self {
+        let pack = &definitions.pickups.health_pack;
+        Self {
+            name: "Practice".to_string(),
+            monster_count: definitions.difficulty(GameDifficulty::Custom).monsters.len(),
+            monster_speed: 1.0,
+            monster_damage: definitions.monster(MonsterKind::Grunt).damage,
+            damage_cooldown: definitions.player.damage_cooldown,
+            heal: pack.heal,
+            pack_spawn_interval: pack.spawn_interval,
+            starting_health: definitions.player.max_health,
+        }
+        .clamped()
+    }
+
+    //某个数值当前的大小
+    fn get(&self, field: CustomField) -> f32 {
+        match field {
+            CustomField::MonsterCount => self.monster_count as f32,
+            CustomField::MonsterSpeed => self.monster_speed,
+            CustomField::MonsterDamage => self.monster_damage as f32,
+            CustomField::DamageCooldown => self.damage_cooldown,
+            CustomField::Heal => self.heal as f32,
+            CustomField::PackSpawnInterval => self.pack_spawn_interval,
+            CustomField::StartingHealth => self.starting_health as f32,
+        }
+    }
+
+    //设置某个数值，超出范围时取最近的边界
+    fn set(&mut self, field: CustomField, value: f32) {
+        let (min, max, _) = field.range();
+        let value = value.clamp(min, max);
+        match field {
+            CustomField::MonsterCount => self.monster_count = value.round() as usize,
+            CustomField::MonsterSpeed => self.monster_speed = value,
+            CustomField::MonsterDamage => self.monster_damage = value.round() as i32,
+            CustomField::DamageCooldown => self.damage_cooldown = value,
+            CustomField::Heal => self.heal = value.round() as i32,
+            CustomField::PackSpawnInterval => self.pack_spawn_interval = value,
+            CustomField::StartingHealth => self.starting_health = value.round() as i32,
+        }
+    }
+
+    //把某个数值调大（steps 为正）或调小（steps 为负）若干个步长
+    pub fn adjust(&mut self, field: CustomField, steps: i32) {
+        let (_, _, step) = field.range();
+        self.set(field, self.get(field) + step * steps as f32);
+    }
+
+    //编辑界面中显示的数值
+    pub fn display(&self, field: CustomField) -> String {
+        match field {
+            CustomField::MonsterSpeed => format!("x{:.2}", self.monster_speed),
+            CustomField::DamageCooldown => format!("{:.2}s", self.damage_cooldown),
+            CustomField::PackSpawnInterval => format!("{:.0}s", self.pack_spawn_interval),
+            _ => format!("{}", self.get(field)),
+        }
+    }
+
+    //把所有数值限制在范围之内（手动改过的预设文件里可能有超出范围的数值）
+    fn clamped(mut self) -> Self {
+        for field in ALL_FIELDS {
+            self.set(field, self.get(field));
+        }
+        self.name.truncate(MAX_NAME_LEN);
+        self
+    }
+
+    //把自定义的数值覆盖到定义文件的数值上，得到这一局使用的数值定义
+    pub fn apply(&self, definitions: &Definitions) -> Definitions {
+        let mut definitions = definitions.clone();
+        definitions.player.max_health = self.starting_health;
+        definitions.player.damage_cooldown = self.damage_cooldown;
+        let pack = &mut definitions.pickups.health_pack;
+        pack.heal = self.heal;
+        pack.spawn_interval = self.pack_spawn_interval;
+        for stats in definitions.monsters.values_mut() {
+            stats.move_interval /= self.monster_speed;
+            stats.damage = self.monster_damage;
+        }
+        //开局的怪物按预设里的种类轮流生成，凑够 monster_count 个
+        if let Some(preset) = definitions.difficulties.get_mut(&GameDifficulty::Custom) {
+            let kinds = std::mem::take(&mut preset.monsters);
+            preset.monsters = kinds.into_iter().cycle().take(self.monster_count).collect();
+        }
+        definitions
+    }
+}
+
+//读取保存过的所有预设，文件不存在时返回空的列表；文件损坏时把它改名留着，同样返回空的列表
+pub fn load_presets(path: &str) -> Vec<CustomDifficulty> {
+    let presets: Vec<CustomDifficulty> = load_json_or_recover(path, "预设文件");
+    presets.into_iter().map(CustomDifficulty::clamped).collect()
+}
+
+//保存一个预设（同名的预设会被覆盖），返回保存之后的所有预设
+pub fn save_preset(path: &str, preset: &CustomDifficulty) -> Result<Vec<CustomDifficulty>, String> {
+    let mut presets = load_presets(path);
+    match presets.iter_mut().find(|p| p.name == preset.name) {
+        Some(existing) => *existing = preset.clone(),
+        None => presets.push(preset.clone()),
+    }
+    save_json(path, &presets, "预设文件")?;
+    Ok(presets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_values_override_definitions() {
+        let definitions = Definitions::builtin();
+        let mut custom = CustomDifficulty::new(&definitions);
+        custom.monster_count = 5;
+        custom.monster_speed = 2.0;
+        custom.starting_health = 50;
+        let applied = custom.apply(&definitions);
+
+        assert_eq!(applied.player.max_health, 50);
+        assert_eq!(applied.difficulty(GameDifficulty::Custom).monsters.len(), 5);
+        let grunt = MonsterKind::Grunt;
+        assert_eq!(applied.monster(grunt).move_interval, definitions.monster(grunt).move_interval / 2.0);
+
+        //调整数值时不会超出范围
+        custom.adjust(CustomField::MonsterSpeed, -100);
+        assert_eq!(custom.monster_speed, 0.25);
+    }
+
+    #[test]
+    fn presets_can_be_saved_after_the_file_is_corrupted() {
+        let path = std::env::temp_dir().join(format!("custom_presets_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        std::fs::write(path, "[ not json").unwrap();
+
+        let mut custom = CustomDifficulty::new(&Definitions::builtin());
+        custom.name = "Practice".to_string();
+        assert_eq!(save_preset(path, &custom).unwrap(), vec![custom.clone()]);
+        assert_eq!(load_presets(path), vec![custom]);
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(format!("{}.corrupt", path)).unwrap();
+    }
+}
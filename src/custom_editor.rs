@@ -0,0 +1,189 @@
+use macroquad::prelude::*;
+use crate::custom::{load_presets, save_preset, CustomDifficulty, CustomField, ALL_FIELDS, MAX_NAME_LEN, PRESETS_PATH};
+use crate::definitions::Definitions;
+
+//编辑界面中的一行
+#[derive(Clone, Copy, PartialEq)]
+enum Row {
+    //预设的名字
+    Name,
+    //一个数值
+    Field(CustomField),
+    //保存预设
+    Save,
+    //开始游戏
+    Start,
+}
+
+//编辑界面的行数：名字、各个数值、保存、开始
+const ROW_COUNT: usize = ALL_FIELDS.len() + 3;
+
+//第 index 行是什么
+fn row(index: usize) -> Row {
+    match index {
+        0 => Row::Name,
+        i if i <= ALL_FIELDS.len() => Row::Field(ALL_FIELDS[i - 1]),
+        i if i == ALL_FIELDS.len() + 1 => Row::Save,
+        _ => Row::Start,
+    }
+}
+
+//编辑界面的操作结果
+pub enum EditorAction {
+    //用编辑好的数值开始游戏
+    Start(CustomDifficulty),
+    //返回菜单
+    Back,
+}
+
+//自定义难度的编辑界面
+pub struct CustomEditor {
+    //正在编辑的数值
+    custom: CustomDifficulty,
+    //选中的行
+    selected: usize,
+    //保存过的预设
+    presets: Vec<CustomDifficulty>,
+    //上一次载入的预设的序号，TAB 从它的下一个开始载入
+    preset_index: Option<usize>,
+    //保存、载入预设的结果提示
+    status: String,
+}
+
+impl CustomEditor {
+    pub fn new(definitions: &Definitions) -> Self {
+        Self {
+            custom: CustomDifficulty::new(definitions),
+            selected: ROW_COUNT - 1,
+            //预设文件有误时打印错误信息，把它改名留着，当作没有预设
+            presets: load_presets(PRESETS_PATH),
+            preset_index: None,
+            status: String::new(),
+        }
+    }
+
+    pub fn update(&mut self) -> Option<EditorAction> {
+        //名字的输入：选中名字这一行时，字母、数字、空格、'-' 和 '_' 追加到名字后面，退格键删除一个字符
+        while let Some(c) = get_char_pressed() {
+            let allowed = c.is_ascii_alphanumeric() || c == ' ' || c == '-' || c == '_';
+            if row(self.selected) == Row::Name && allowed && self.custom.name.len() < MAX_NAME_LEN {
+                self.custom.name.push(c);
+            }
+        }
+        if row(self.selected) == Row::Name && is_key_pressed(KeyCode::Backspace) {
+            self.custom.name.pop();
+        }
+
+        if is_key_pressed(KeyCode::Escape) {
+            return Some(EditorAction::Back);
+        }
+
+        //选择要编辑的行
+        if is_key_pressed(KeyCode::Up) {
+            self.selected = (self.selected + ROW_COUNT - 1) % ROW_COUNT;
+        }
+        if is_key_pressed(KeyCode::Down) {
+            self.selected = (self.selected + 1) % ROW_COUNT;
+        }
+
+        //调整选中的数值
+        if let Row::Field(field) = row(self.selected) {
+            if is_key_pressed(KeyCode::Left) {
+                self.custom.adjust(field, -1);
+            }
+            if is_key_pressed(KeyCode::Right) {
+                self.custom.adjust(field, 1);
+            }
+        }
+
+        //依次载入保存过的预设
+        if is_key_pressed(KeyCode::Tab) {
+            if self.presets.is_empty() {
+                self.status = "No saved presets yet".to_string();
+            } else {
+                let index = self.preset_index.map_or(0, |i| (i + 1) % self.presets.len());
+                self.preset_index = Some(index);
+                self.custom = self.presets[index].clone();
+                self.status = format!("Loaded preset \"{}\"", self.custom.name);
+            }
+        }
+
+        if is_key_pressed(KeyCode::Enter) {
+            if row(self.selected) != Row::Save {
+                return Some(EditorAction::Start(self.custom.clone()));
+            }
+            if self.custom.name.trim().is_empty() {
+                self.status = "Type a name before saving".to_string();
+            } else {
+                match save_preset(PRESETS_PATH, &self.custom) {
+                    Ok(presets) => {
+                        self.preset_index = presets.iter().position(|p| p.name == self.custom.name);
+                        self.presets = presets;
+                        self.status = format!("Saved preset \"{}\"", self.custom.name);
+                    }
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        self.status = "Could not save the preset".to_string();
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    //绘制编辑界面（背景由菜单负责绘制）
+    pub fn draw(&self) {
+        draw_rectangle(0.0, 0.0, screen_width(), screen_height(), Color::new(0.0, 0.0, 0.0, 0.7));
+
+        let title = "Custom Difficulty";
+        let title_size = 48.0;
+        let title_width = measure_text(title, None, title_size as u16, 1.0).width;
+        draw_text(title, screen_width() / 2.0 - title_width / 2.0 + 2.0, 90.0 + 2.0, title_size, BLACK);
+        draw_text(title, screen_width() / 2.0 - title_width / 2.0, 90.0, title_size, YELLOW);
+
+        //每一行的文字，选中的一行前面加上箭头
+        let row_size = 30.0;
+        for index in 0..ROW_COUNT {
+            let text = match row(index) {
+                Row::Name => format!("Name: {}", self.custom.name),
+                Row::Field(field) => format!("{}: < {} >", field.label(), self.custom.display(field)),
+                Row::Save => "Save preset".to_string(),
+                Row::Start => "Start".to_string(),
+            };
+            let (text, color) = if index == self.selected {
+                (format!("> {} <", text), GREEN)
+            } else {
+                (text, WHITE)
+            };
+            let width = measure_text(&text, None, row_size as u16, 1.0).width;
+            let y = 150.0 + index as f32 * 40.0;
+            draw_text(&text, screen_width() / 2.0 - width / 2.0 + 1.0, y + 1.0, row_size, BLACK);
+            draw_text(&text, screen_width() / 2.0 - width / 2.0, y, row_size, color);
+        }
+
+        //保存、载入预设的结果
+        if !self.status.is_empty() {
+            let status_size = 22.0;
+            let width = measure_text(&self.status, None, status_size as u16, 1.0).width;
+            draw_text(
+                &self.status,
+                screen_width() / 2.0 - width / 2.0,
+                150.0 + ROW_COUNT as f32 * 40.0,
+                status_size,
+                ORANGE,
+            );
+        }
+
+        let hint = "UP/DOWN: select   LEFT/RIGHT: change   TAB: load preset   ENTER: start   ESC: back";
+        let hint_size = 18.0;
+        let hint_width = measure_text(hint, None, hint_size as u16, 1.0).width;
+        draw_text(
+            hint,
+            screen_width() / 2.0 - hint_width / 2.0,
+            screen_height() - 30.0,
+            hint_size,
+            Color::new(0.4, 0.8, 1.0, 1.0),
+        );
+    }
+}
@@ -0,0 +1,249 @@
+//每日挑战模块：用当天的日期算出种子、地牢风格和两条规则修改，同一天所有人玩到的都是完全相同的一局
+//日期按本地时间计算，挑战在本地的午夜换成新的一天；只用种子的二进制位来挑选规则，不依赖随机数生成器的实现
+//每天的成绩（次数、最高分、最长存活时间）保存在 data/daily_history.json 中，菜单上显示连续挑战的天数
+use serde::{Deserialize, Serialize};
+use crate::date::Date;
+use crate::definitions::Definitions;
+use crate::dungeon::DungeonStyle;
+use crate::game::{RunStats, VIEW_RADIUS};
+use crate::menu::GameDifficulty;
+use crate::storage::{load_json_or_recover, save_json};
+
+//每日挑战的成绩文件
+pub const DAILY_HISTORY_PATH: &str = "data/daily_history.json";
+
+//每日挑战的规则修改
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Modifier {
+    //所有怪物的速度翻倍
+    DoubleSpeed,
+    //不刷新血包
+    NoHealthPacks,
+    //玩家的生命值减半，攻击的伤害翻倍
+    GlassCannon,
+    //开局的怪物数量翻倍
+    Swarm,
+    //怪物的视野和玩家一样远
+    KeenEyes,
+    //首领提前到 30 秒出场
+    EarlyBoss,
+}
+
+//所有的规则修改
+const ALL_MODIFIERS: [Modifier; 6] = [
+    Modifier::DoubleSpeed,
+    Modifier::NoHealthPacks,
+    Modifier::GlassCannon,
+    Modifier::Swarm,
+    Modifier::KeenEyes,
+    Modifier::EarlyBoss,
+];
+
+impl Modifier {
+    pub fn name(self) -> &'static str {
+        match self {
+            Modifier::DoubleSpeed => "Double Speed",
+            Modifier::NoHealthPacks => "No Health Packs",
+            Modifier::GlassCannon => "Glass Cannon",
+            Modifier::Swarm => "Swarm",
+            Modifier::KeenEyes => "Keen Eyes",
+            Modifier::EarlyBoss => "Early Boss",
+        }
+    }
+
+    //把这条规则修改应用到 difficulty 难度要使用的数值定义上
+    fn apply(self, definitions: &mut Definitions, difficulty: GameDifficulty) {
+        match self {
+            Modifier::DoubleSpeed => {
+                for stats in definitions.monsters.values_mut() {
+                    stats.move_interval /= 2.0;
+                }
+            }
+            //刷新的间隔设成最大的数，一局里永远等不到（写进录像也不会出问题）
+            Modifier::NoHealthPacks => definitions.pickups.health_pack.spawn_interval = f32::MAX,
+            Modifier::GlassCannon => {
+                definitions.player.max_health = (definitions.player.max_health / 2).max(1);
+                definitions.player.attack_damage *= 2;
+            }
+            Modifier::Swarm => {
+                if let Some(preset) = definitions.difficulties.get_mut(&difficulty) {
+                    preset.monsters = preset.monsters.repeat(2);
+                }
+            }
+            Modifier::KeenEyes => {
+                for stats in definitions.monsters.values_mut() {
+                    stats.behaviour.sight_radius = VIEW_RADIUS as f32;
+                }
+            }
+            Modifier::EarlyBoss => definitions.boss.spawn_time = definitions.boss.spawn_time.min(30.0),
+        }
+    }
+}
+
+//某一天的挑战
+#[derive(Clone, Debug, PartialEq)]
+pub struct DailyChallenge {
+    pub date: Date,
+    pub seed: u64,
+    pub difficulty: GameDifficulty,
+    pub dungeon: DungeonStyle,
+    //这一天的两条规则修改
+    pub modifiers: Vec<Modifier>,
+}
+
+impl DailyChallenge {
+    //根据日期生成这一天的挑战
+    pub fn for_date(date: Date) -> Self {
+        //把日期打散成种子（splitmix64），相邻两天的种子也完全不同
+        let mut seed = (date.days() as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+        seed = (seed ^ (seed >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        seed = (seed ^ (seed >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        seed ^= seed >> 31;
+
+        //从种子的不同位上挑两条不重复的规则修改
+        let mut pool = ALL_MODIFIERS.to_vec();
+        let first = pool.remove((seed >> 8) as usize % pool.len());
+        let second = pool.remove((seed >> 16) as usize % pool.len());
+        Self {
+            date,
+            seed,
+            difficulty: GameDifficulty::Medium,
+            dungeon: if seed & 1 == 0 { DungeonStyle::Rooms } else { DungeonStyle::Caves },
+            modifiers: vec![first, second],
+        }
+    }
+
+    //今天的挑战
+    pub fn today() -> Self {
+        Self::for_date(Date::today())
+    }
+
+    //规则修改的名字，例如 "Double Speed + Swarm"
+    pub fn describe(&self) -> String {
+        self.modifiers.iter().map(|m| m.name()).collect::<Vec<_>>().join(" + ")
+    }
+
+    //把这一天的规则修改应用到定义文件的数值上，得到这一局使用的数值定义
+    pub fn apply(&self, definitions: &Definitions) -> Definitions {
+        let mut definitions = definitions.clone();
+        for modifier in &self.modifiers {
+            modifier.apply(&mut definitions, self.difficulty);
+        }
+        definitions
+    }
+}
+
+//某一天的成绩
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DailyResult {
+    pub date: Date,
+    //这一天挑战的次数
+    pub attempts: u32,
+    pub best_score: u32,
+    //最长的存活时间（秒）
+    pub best_time: f32,
+}
+
+//每天的成绩记录
+#[derive(Default, Serialize, Deserialize)]
+pub struct DailyHistory {
+    //按日期排列的成绩
+    #[serde(default)]
+    results: Vec<DailyResult>,
+}
+
+impl DailyHistory {
+    //读取成绩记录，文件不存在或者损坏时返回空的记录
+    pub fn load_or_recover(path: &str) -> Self {
+        let mut history: Self = load_json_or_recover(path, "每日挑战记录");
+        history.results.sort_by_key(|r| r.date);
+        history
+    }
+
+    //把成绩记录保存到文件
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        save_json(path, self, "每日挑战记录")
+    }
+
+    //某一天的成绩，这一天没有挑战过时返回 None
+    pub fn result(&self, date: Date) -> Option<&DailyResult> {
+        self.results.iter().find(|r| r.date == date)
+    }
+
+    //记录 date 这一天的一次挑战
+    pub fn record(&mut self, date: Date, stats: &RunStats) {
+        match self.results.iter_mut().find(|r| r.date == date) {
+            Some(result) => {
+                result.attempts += 1;
+                result.best_score = result.best_score.max(stats.score.total);
+                result.best_time = result.best_time.max(stats.game_time);
+            }
+            None => {
+                self.results.push(DailyResult {
+                    date,
+                    attempts: 1,
+                    best_score: stats.score.total,
+                    best_time: stats.game_time,
+                });
+                self.results.sort_by_key(|r| r.date);
+            }
+        }
+    }
+
+    //到 today 为止连续挑战的天数（今天还没挑战时从昨天开始算，连续记录还没有断）
+    pub fn streak(&self, today: Date) -> usize {
+        let played = |days: i64| self.result(Date::from_days(days)).is_some();
+        let mut day = today.days();
+        if !played(day) {
+            day -= 1;
+        }
+        let mut streak = 0;
+        while played(day) {
+            streak += 1;
+            day -= 1;
+        }
+        streak
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scoring::ScoreBreakdown;
+
+    #[test]
+    fn same_date_gives_the_same_challenge() {
+        let date = Date { year: 2024, month: 3, day: 1 };
+        assert_eq!(Date::from_days(date.days()), date);
+        let challenge = DailyChallenge::for_date(date);
+        assert_eq!(challenge, DailyChallenge::for_date(date));
+        assert_ne!(challenge.seed, DailyChallenge::for_date(Date::from_days(date.days() + 1)).seed);
+        assert_ne!(challenge.modifiers[0], challenge.modifiers[1]);
+    }
+
+    #[test]
+    fn streak_counts_consecutive_days() {
+        let stats = RunStats {
+            seed: 1,
+            game_time: 30.0,
+            health_packs_collected: 0,
+            monster_attacks: 0,
+            kills: 0,
+            boss_defeated: false,
+            best_wave: 1,
+            difficulty: GameDifficulty::Medium,
+            score: ScoreBreakdown::default(),
+        };
+        let today = Date { year: 2024, month: 3, day: 1 };
+        let mut history = DailyHistory::default();
+        for offset in [-5, -2, -1] {
+            history.record(Date::from_days(today.days() + offset), &stats);
+        }
+        //今天还没挑战，从昨天开始算（跨过了 2 月 29 日）
+        assert_eq!(history.streak(today), 2);
+        history.record(today, &stats);
+        history.record(today, &stats);
+        assert_eq!(history.streak(today), 3);
+        assert_eq!(history.result(today).unwrap().attempts, 2);
+    }
+}
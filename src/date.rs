@@ -0,0 +1,75 @@
+//日期模块：用系统时间算出今天的日期（按本地时间计算，本地的午夜换一天）
+//本地时间和 UTC 相差多少由 chrono 向操作系统查询（包括夏令时），查不到时按 UTC 计算
+use std::fmt;
+use chrono::{LocalResult, Local, TimeZone};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Date {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+}
+
+impl Date {
+    //今天的日期（系统时间早于 1970 年时当作 1970-01-01）
+    pub fn today() -> Self {
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0);
+        Self::from_days((seconds + utc_offset(seconds)).div_euclid(86_400))
+    }
+
+    //从 1970-01-01 起的第 days 天的日期（公历的换算方法）
+    pub fn from_days(days: i64) -> Self {
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z.rem_euclid(146_097);
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = (year_of_era + era * 400 + if month <= 2 { 1 } else { 0 }) as i32;
+        Self { year, month, day }
+    }
+
+    //这个日期是从 1970-01-01 起的第几天（from_days 的反过程）
+    pub fn days(&self) -> i64 {
+        let year = self.year as i64 - if self.month <= 2 { 1 } else { 0 };
+        let era = year.div_euclid(400);
+        let year_of_era = year.rem_euclid(400);
+        let month = self.month as i64;
+        let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+}
+
+//在 UTC 时间戳 seconds 那一刻，本地时间比 UTC 快多少秒（由 chrono 向操作系统查询，查不到时按 UTC 计算）
+fn utc_offset(seconds: i64) -> i64 {
+    match Local.timestamp_opt(seconds, 0) {
+        LocalResult::Single(time) | LocalResult::Ambiguous(time, _) => time.offset().local_minus_utc() as i64,
+        LocalResult::None => 0,
+    }
+}
+
+//显示为 2024-05-17 的格式
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn local_date_is_at_most_a_day_from_utc() {
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64;
+        //时区最多比 UTC 快 14 小时、慢 12 小时
+        assert!((-12 * 3600..=14 * 3600).contains(&utc_offset(seconds)));
+        assert!((Date::today().days() - seconds / 86_400).abs() <= 1);
+        assert_eq!(Date::from_days(19_000).to_string(), "2022-01-08");
+    }
+}
@@ -0,0 +1,372 @@
+//数值定义模块：玩家的基础数值、拾取物、怪物种类和难度预设都放在 data/definitions.toml 里
+//启动时读取并检查一遍，数值不合法时给出可读的错误信息，调整平衡不需要改代码
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::game::VIEW_RADIUS;
+use crate::dynamic::DynamicDef;
+use crate::menu::{GameDifficulty, ALL_DIFFICULTIES};
+use crate::monster_kind::{MonsterKind, MonsterStats, MovementPattern, ALL_KINDS};
+use crate::pathfinding::Connectivity;
+use crate::scoring::ScoringDef;
+
+//默认的定义文件
+pub const DEFAULT_PATH: &str = "data/definitions.toml";
+//编译进程序里的一份定义文件，外部文件有误时使用
+const BUILTIN: &str = include_str!("../data/definitions.toml");
+
+//玩家的基础数值
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PlayerDef {
+    //最大生命值（也是开局时的生命值）
+    pub max_health: i32,
+    //被怪物攻击之后的无敌时间（秒）
+    pub damage_cooldown: f32,
+    //近战攻击的伤害
+    pub attack_damage: i32,
+    //两次攻击之间的最短间隔（秒）
+    pub attack_cooldown: f32,
+    //没被打死的怪物被击退的格数
+    pub knockback: usize,
+}
+
+//血包的数值
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HealthPackDef {
+    //回复的生命值
+    pub heal: i32,
+    //吃到血包之后强化状态持续的时间（秒）
+    pub power_up_time: f32,
+    //刷新血包的间隔（秒）
+    pub spawn_interval: f32,
+}
+
+//所有拾取物的数值
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Pickups {
+    pub health_pack: HealthPackDef,
+}
+
+//怪物波次的数值
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WaveDef {
+    //第 2 波出现的时间，也是前两波之间的间隔（秒）
+    pub interval: f32,
+    //之后每一波的间隔都乘以 shrink（0 到 1 之间），但不会短于 min_interval
+    pub shrink: f32,
+    pub min_interval: f32,
+    //刷怪之前预警的时间（秒）
+    pub telegraph_time: f32,
+    //第 2 波的怪物数量，之后每一波多 growth 个（可以是小数，累计满 1 个才多刷一个）
+    pub size: usize,
+    pub growth: f32,
+    //场上怪物数量的上限
+    pub max_alive: usize,
+    //波次中可能出现的怪物种类
+    pub kinds: Vec<MonsterKind>,
+}
+
+//一种难度的预设
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DifficultyPreset {
+    //开局时生成的怪物种类
+    pub monsters: Vec<MonsterKind>,
+    //一格一格走的怪物的移动方式
+    pub movement: Connectivity,
+    //之后刷出来的怪物波次
+    pub waves: WaveDef,
+}
+
+//首领的攻击方式
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum BossPattern {
+    //在身边召唤 count 个 kind 种类的怪物
+    Summon { kind: MonsterKind, count: usize },
+    //向八个方向同时射出弹丸
+    Burst { damage: i32, speed: f32, range: usize },
+}
+
+//首领的一个阶段
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BossPhase {
+    //生命值降到这个比例（0 到 1）以下时进入这个阶段
+    pub health_below: f32,
+    //两次发动攻击之间的间隔（秒）
+    pub interval: f32,
+    pub pattern: BossPattern,
+}
+
+//首领的数值（首领本身的速度、伤害、生命值等写在 [monsters.Boss] 中）
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BossDef {
+    //游戏进行多少秒之后出现
+    pub spawn_time: f32,
+    //按生命值从高到低排列的各个阶段
+    pub phases: Vec<BossPhase>,
+}
+
+//定义文件的内容（反序列化之后还没有检查过）
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DefinitionsFile {
+    player: PlayerDef,
+    pickups: Pickups,
+    monsters: HashMap<MonsterKind, MonsterStats>,
+    boss: BossDef,
+    dynamic: DynamicDef,
+    scoring: ScoringDef,
+    difficulties: HashMap<GameDifficulty, DifficultyPreset>,
+}
+
+//检查过的全部数值定义
+//录像里也会保存一份，这样改了数值之后旧的录像仍然能正确播放
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "DefinitionsFile")]
+pub struct Definitions {
+    pub player: PlayerDef,
+    pub pickups: Pickups,
+    pub monsters: HashMap<MonsterKind, MonsterStats>,
+    pub boss: BossDef,
+    //动态难度的调整规则（难度预设 Dynamic 是调整的起点）
+    pub dynamic: DynamicDef,
+    //计分的规则
+    pub scoring: ScoringDef,
+    pub difficulties: HashMap<GameDifficulty, DifficultyPreset>,
+}
+
+impl Definitions {
+    //编译进程序里的默认数值
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN).expect("内置的定义文件有误")
+    }
+
+    //从文件读取定义
+    //图片在启动时才加载，这里先确认图片文件存在，写错的路径也能得到可读的错误信息
+    //（这个检查不放在解析里：录像和存档中的定义只用来模拟，不需要图片）
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("无法读取定义文件 {}: {}", path, e))?;
+        let definitions = Self::parse(&text).map_err(|e| format!("定义文件 {} 有误: {}", path, e))?;
+        for kind in ALL_KINDS {
+            let texture = &definitions.monster(kind).texture;
+            check(
+                Path::new(texture).is_file(),
+                format!("定义文件 {} 有误: monsters.{:?}.texture（\"{}\"）", path, kind, texture),
+                "找不到这个图片文件",
+            )?;
+        }
+        Ok(definitions)
+    }
+
+    //从 TOML 文本解析定义
+    pub fn parse(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|e| e.to_string())
+    }
+
+    //某种怪物的数值（检查时已经保证每种怪物都有定义）
+    pub fn monster(&self, kind: MonsterKind) -> &MonsterStats {
+        &self.monsters[&kind]
+    }
+
+    //某个难度的预设（检查时已经保证每个难度都有预设）
+    pub fn difficulty(&self, difficulty: GameDifficulty) -> &DifficultyPreset {
+        &self.difficulties[&difficulty]
+    }
+}
+
+//检查一个数值是否满足条件，不满足时返回错误信息
+fn check(ok: bool, what: String, rule: &str) -> Result<(), String> {
+    if ok { Ok(()) } else { Err(format!("{} {}", what, rule)) }
+}
+
+impl TryFrom<DefinitionsFile> for Definitions {
+    type Error = String;
+
+    fn try_from(file: DefinitionsFile) -> Result<Self, Self::Error> {
+        let player = &file.player;
+        check(player.max_health > 0, format!("player.max_health（{}）", player.max_health), "必须大于 0")?;
+        check(player.damage_cooldown >= 0.0, format!("player.damage_cooldown（{}）", player.damage_cooldown), "不能是负数")?;
+        check(player.attack_damage > 0, format!("player.attack_damage（{}）", player.attack_damage), "必须大于 0")?;
+        check(player.attack_cooldown >= 0.0, format!("player.attack_cooldown（{}）", player.attack_cooldown), "不能是负数")?;
+
+        let pack = &file.pickups.health_pack;
+        check(pack.heal > 0, format!("pickups.health_pack.heal（{}）", pack.heal), "必须大于 0")?;
+        check(pack.power_up_time >= 0.0, format!("pickups.health_pack.power_up_time（{}）", pack.power_up_time), "不能是负数")?;
+        check(pack.spawn_interval > 0.0, format!("pickups.health_pack.spawn_interval（{}）", pack.spawn_interval), "必须大于 0")?;
+
+        for kind in ALL_KINDS {
+            let Some(stats) = file.monsters.get(&kind) else {
+                return Err(format!("缺少怪物 {:?} 的定义（[monsters.{:?}]）", kind, kind));
+            };
+            let field = |name: &str| format!("monsters.{:?}.{}", kind, name);
+            check(stats.move_interval > 0.0, format!("{}（{}）", field("move_interval"), stats.move_interval), "必须大于 0")?;
+            check(stats.damage >= 0, format!("{}（{}）", field("damage"), stats.damage), "不能是负数")?;
+            check(stats.health > 0, format!("{}（{}）", field("health"), stats.health), "必须大于 0")?;
+            if let MovementPattern::Rook { slide } = stats.movement {
+                check(slide > 0, format!("{}（slide = {}）", field("movement"), slide), "至少要滑行 1 格")?;
+            }
+            //视野是对称的，怪物用玩家的视野判断能否看见玩家，所以怪物看不到比玩家更远的地方
+            let behaviour = &stats.behaviour;
+            check(
+                behaviour.sight_radius > 0.0 && behaviour.sight_radius <= VIEW_RADIUS as f32,
+                format!("{}（{}）", field("behaviour.sight_radius"), behaviour.sight_radius),
+                &format!("必须在 0 到 {} 之间", VIEW_RADIUS),
+            )?;
+            check(behaviour.search_time >= 0.0, format!("{}（{}）", field("behaviour.search_time"), behaviour.search_time), "不能是负数")?;
+            if let Some(ranged) = &stats.ranged {
+                check(ranged.damage >= 0, format!("{}（{}）", field("ranged.damage"), ranged.damage), "不能是负数")?;
+                check(ranged.cooldown > 0.0, format!("{}（{}）", field("ranged.cooldown"), ranged.cooldown), "必须大于 0")?;
+                check(ranged.speed > 0.0, format!("{}（{}）", field("ranged.speed"), ranged.speed), "必须大于 0")?;
+                check(ranged.range > 0, format!("{}（{}）", field("ranged.range"), ranged.range), "必须大于 0")?;
+            }
+        }
+
+        let boss = &file.boss;
+        check(boss.spawn_time >= 0.0, format!("boss.spawn_time（{}）", boss.spawn_time), "不能是负数")?;
+        let mut previous = 1.0;
+        for (i, phase) in boss.phases.iter().enumerate() {
+            let field = |name: &str| format!("boss.phases[{}].{}", i, name);
+            check(
+                phase.health_below > 0.0 && phase.health_below <= previous,
+                format!("{}（{}）", field("health_below"), phase.health_below),
+                &format!("必须在 0 到 {} 之间（各个阶段要按生命值从高到低排列）", previous),
+            )?;
+            previous = phase.health_below;
+            check(phase.interval > 0.0, format!("{}（{}）", field("interval"), phase.interval), "必须大于 0")?;
+            match phase.pattern {
+                BossPattern::Summon { kind, count } => {
+                    check(kind != MonsterKind::Boss, field("pattern.Summon.kind"), "不能召唤首领")?;
+                    check(count > 0, format!("{}（{}）", field("pattern.Summon.count"), count), "必须大于 0")?;
+                }
+                BossPattern::Burst { damage, speed, range } => {
+                    check(damage >= 0, format!("{}（{}）", field("pattern.Burst.damage"), damage), "不能是负数")?;
+                    check(speed > 0.0, format!("{}（{}）", field("pattern.Burst.speed"), speed), "必须大于 0")?;
+                    check(range > 0, format!("{}（{}）", field("pattern.Burst.range"), range), "必须大于 0")?;
+                }
+            }
+        }
+
+        let dynamic = &file.dynamic;
+        let field = |name: &str, value: f32| format!("dynamic.{}（{}）", name, value);
+        check(dynamic.evaluate_interval > 0.0, field("evaluate_interval", dynamic.evaluate_interval), "必须大于 0")?;
+        check(dynamic.step > 0.0, field("step", dynamic.step), "必须大于 0")?;
+        check(
+            dynamic.min_pressure > 0.0 && dynamic.min_pressure <= 1.0,
+            field("min_pressure", dynamic.min_pressure),
+            "必须在 0 到 1 之间",
+        )?;
+        check(dynamic.max_pressure >= 1.0, field("max_pressure", dynamic.max_pressure), "不能小于 1")?;
+        check(
+            dynamic.target_health > 0.0 && dynamic.target_health < 1.0,
+            field("target_health", dynamic.target_health),
+            "必须在 0 到 1 之间",
+        )?;
+        check(dynamic.target_hits_per_minute > 0.0, field("target_hits_per_minute", dynamic.target_hits_per_minute), "必须大于 0")?;
+        check(dynamic.target_packs_per_minute > 0.0, field("target_packs_per_minute", dynamic.target_packs_per_minute), "必须大于 0")?;
+        check(dynamic.tolerance >= 0.0, field("tolerance", dynamic.tolerance), "不能是负数")?;
+
+        let scoring = &file.scoring;
+        check(scoring.points_per_second >= 0.0, format!("scoring.points_per_second（{}）", scoring.points_per_second), "不能是负数")?;
+        check(scoring.par_hits_per_minute >= 0.0, format!("scoring.par_hits_per_minute（{}）", scoring.par_hits_per_minute), "不能是负数")?;
+        for difficulty in ALL_DIFFICULTIES {
+            let Some(&multiplier) = scoring.multipliers.get(&difficulty) else {
+                return Err(format!("缺少难度 {:?} 的分数倍率（scoring.multipliers.{:?}）", difficulty, difficulty));
+            };
+            check(multiplier > 0.0, format!("scoring.multipliers.{:?}（{}）", difficulty, multiplier), "必须大于 0")?;
+        }
+
+        for difficulty in ALL_DIFFICULTIES {
+            let Some(preset) = file.difficulties.get(&difficulty) else {
+                return Err(format!("缺少难度 {:?} 的预设（[difficulties.{:?}]）", difficulty, difficulty));
+            };
+            let field = |name: &str| format!("difficulties.{:?}.{}", difficulty, name);
+            check(!preset.monsters.is_empty(), field("monsters"), "至少要有一个怪物")?;
+
+            let waves = &preset.waves;
+            check(waves.interval > 0.0, format!("{}（{}）", field("waves.interval"), waves.interval), "必须大于 0")?;
+            check(
+                waves.min_interval > 0.0 && waves.min_interval <= waves.interval,
+                format!("{}（{}）", field("waves.min_interval"), waves.min_interval),
+                &format!("必须在 0 到 interval（{}）之间", waves.interval),
+            )?;
+            check(waves.shrink > 0.0 && waves.shrink <= 1.0, format!("{}（{}）", field("waves.shrink"), waves.shrink), "必须在 0 到 1 之间")?;
+            check(
+                waves.telegraph_time >= 0.0 && waves.telegraph_time < waves.min_interval,
+                format!("{}（{}）", field("waves.telegraph_time"), waves.telegraph_time),
+                "不能是负数，并且必须小于 min_interval",
+            )?;
+            check(waves.size > 0, format!("{}（{}）", field("waves.size"), waves.size), "必须大于 0")?;
+            check(waves.growth >= 0.0, format!("{}（{}）", field("waves.growth"), waves.growth), "不能是负数")?;
+            check(waves.max_alive > 0, format!("{}（{}）", field("waves.max_alive"), waves.max_alive), "必须大于 0")?;
+            check(
+                !waves.kinds.is_empty() && !waves.kinds.contains(&MonsterKind::Boss),
+                field("waves.kinds"),
+                "至少要有一种怪物，并且不能包含首领",
+            )?;
+        }
+
+        Ok(Self {
+            player: file.player,
+            pickups: file.pickups,
+            monsters: file.monsters,
+            boss: file.boss,
+            dynamic: file.dynamic,
+            scoring: file.scoring,
+            difficulties: file.difficulties,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_definitions_are_valid() {
+        let definitions = Definitions::builtin();
+        assert_eq!(definitions.monsters.len(), ALL_KINDS.len());
+    }
+
+    #[test]
+    fn invalid_values_are_reported() {
+        let text = BUILTIN.replace("move_interval = 0.3", "move_interval = 0.0");
+        let error = Definitions::parse(&text).unwrap_err();
+        assert!(error.contains("monsters.Runner.move_interval"), "{}", error);
+
+        let text = BUILTIN.replace("sight_radius = 8.0", "sight_radius = 12.0");
+        let error = Definitions::parse(&text).unwrap_err();
+        assert!(error.contains("monsters.Ghost.behaviour.sight_radius"), "{}", error);
+
+        //拼错的键不会被悄悄忽略
+        let text = BUILTIN.replace("ranged = { damage = 10", "range = { damage = 10");
+        let error = Definitions::parse(&text).unwrap_err();
+        assert!(error.contains("unknown field `range`"), "{}", error);
+
+        let text = BUILTIN.replace("[monsters.Ghost]", "[monsters.Wraith]");
+        assert!(Definitions::parse(&text).is_err());
+    }
+
+    #[test]
+    fn missing_textures_are_reported_on_load() {
+        let path = std::env::temp_dir().join(format!("definitions_texture_{}.toml", std::process::id()));
+        let path = path.to_str().unwrap();
+        //解析本身不检查图片，录像和存档里的定义在别的机器上也能读
+        let text = BUILTIN.replace("assets/monster_ghost.png", "assets/monster_gohst.png");
+        assert!(Definitions::parse(&text).is_ok());
+
+        fs::write(path, &text).unwrap();
+        let error = Definitions::load(path).unwrap_err();
+        assert!(error.contains("monsters.Ghost.texture"), "{}", error);
+        fs::write(path, BUILTIN).unwrap();
+        assert!(Definitions::load(path).is_ok());
+        fs::remove_file(path).unwrap();
+    }
+}
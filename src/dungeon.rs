@@ -0,0 +1,270 @@
+//地牢生成模块：根据这一局的随机种子生成地图，让每一局的地形都不一样
+//两种风格：房间 + 走廊、元胞自动机生成的洞穴
+//生成的地图保证：所有地板都能从玩家出生点走到；怪物出生点不会紧挨着玩家
+use ::rand::{Rng, SeedableRng};
+use crate::rng::GameRng;
+use serde::{Deserialize, Serialize};
+use crate::level::Level;
+use crate::map::{Map, Tile};
+
+//生成地图的大小
+pub const DUNGEON_WIDTH: usize = 40;
+pub const DUNGEON_HEIGHT: usize = 26;
+
+//房间的数量上限和尝试放置房间的次数
+const MAX_ROOMS: usize = 10;
+const ROOM_ATTEMPTS: usize = 80;
+//洞穴初始时每个格子是墙壁的概率，以及平滑的次数
+const CAVE_WALL_CHANCE: f64 = 0.48;
+const CAVE_SMOOTH_STEPS: usize = 5;
+//怪物出生点数量，以及出生点与玩家之间的最小距离（按格子数计算，斜着走也算一步）
+const SPAWN_COUNT: usize = 8;
+const MIN_SPAWN_DISTANCE: usize = 5;
+
+//地牢的风格
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum DungeonStyle {
+    Rooms,
+    Caves,
+}
+
+impl DungeonStyle {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DungeonStyle::Rooms => "Rooms",
+            DungeonStyle::Caves => "Caves",
+        }
+    }
+}
+
+//矩形房间
+#[derive(Clone, Copy)]
+struct Room {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Room {
+    fn center(&self) -> (usize, usize) {
+        (self.x + self.width / 2, self.y + self.height / 2)
+    }
+
+    //两个房间是否重叠（四周额外留出一格，避免房间贴在一起）
+    fn intersects(&self, other: &Room) -> bool {
+        self.x <= other.x + other.width
+            && other.x <= self.x + self.width
+            && self.y <= other.y + other.height
+            && other.y <= self.y + self.height
+    }
+}
+
+//用种子生成一个关卡
+pub fn generate(style: DungeonStyle, seed: u64) -> Level {
+    let mut rng = GameRng::seed_from_u64(seed);
+    let (mut map, player_start) = match style {
+        DungeonStyle::Rooms => generate_rooms(&mut rng),
+        DungeonStyle::Caves => generate_caves(&mut rng),
+    };
+
+    //走不到的地板全部变成墙壁，保证整张地图是连通的
+    let mut reachable = Map::new(map.width(), map.height(), Tile::Wall);
+    for (x, y) in map.reachable_from(player_start.0, player_start.1) {
+        reachable.set(x, y, Tile::Floor);
+    }
+    map = reachable;
+
+    let monster_spawns = pick_monster_spawns(&map, player_start, &mut rng);
+
+    Level {
+        name: format!("{} #{}", style.name(), seed),
+        map,
+        player_start,
+        monster_spawns,
+        health_pack_zones: Vec::new(),
+    }
+}
+
+//房间 + 走廊：随机放置互不重叠的房间，再用L形走廊把相邻的房间依次连起来
+fn generate_rooms(rng: &mut GameRng) -> (Map, (usize, usize)) {
+    let mut map = Map::new(DUNGEON_WIDTH, DUNGEON_HEIGHT, Tile::Wall);
+    let mut rooms: Vec<Room> = Vec::new();
+
+    for _ in 0..ROOM_ATTEMPTS {
+        if rooms.len() >= MAX_ROOMS {
+            break;
+        }
+        let width = rng.gen_range(4..10);
+        let height = rng.gen_range(3..7);
+        let room = Room {
+            x: rng.gen_range(1..DUNGEON_WIDTH - width - 1),
+            y: rng.gen_range(1..DUNGEON_HEIGHT - height - 1),
+            width,
+            height,
+        };
+        if rooms.iter().any(|other| room.intersects(other)) {
+            continue;
+        }
+
+        carve_rect(&mut map, room);
+        if let Some(previous) = rooms.last() {
+            carve_corridor(&mut map, previous.center(), room.center(), rng.gen_bool(0.5));
+        }
+        rooms.push(room);
+    }
+
+    //玩家从第一个房间的中心出发
+    (map, rooms[0].center())
+}
+
+//把房间范围内的格子都挖成地板
+fn carve_rect(map: &mut Map, room: Room) {
+    for y in room.y..room.y + room.height {
+        for x in room.x..room.x + room.width {
+            map.set(x, y, Tile::Floor);
+        }
+    }
+}
+
+//挖一条连接两点的L形走廊，horizontal_first 决定先横着挖还是先竖着挖
+fn carve_corridor(map: &mut Map, from: (usize, usize), to: (usize, usize), horizontal_first: bool) {
+    let corner = if horizontal_first { (to.0, from.1) } else { (from.0, to.1) };
+    for (a, b) in [(from, corner), (corner, to)] {
+        for y in a.1.min(b.1)..=a.1.max(b.1) {
+            for x in a.0.min(b.0)..=a.0.max(b.0) {
+                map.set(x, y, Tile::Floor);
+            }
+        }
+    }
+}
+
+//洞穴：先随机撒墙壁，再用元胞自动机反复平滑，最后只保留最大的一片连通区域
+fn generate_caves(rng: &mut GameRng) -> (Map, (usize, usize)) {
+    let mut map = Map::walled(DUNGEON_WIDTH, DUNGEON_HEIGHT);
+    for y in 1..DUNGEON_HEIGHT - 1 {
+        for x in 1..DUNGEON_WIDTH - 1 {
+            if rng.gen_bool(CAVE_WALL_CHANCE) {
+                map.set(x, y, Tile::Wall);
+            }
+        }
+    }
+
+    for _ in 0..CAVE_SMOOTH_STEPS {
+        let mut next = map.clone();
+        for y in 1..DUNGEON_HEIGHT - 1 {
+            for x in 1..DUNGEON_WIDTH - 1 {
+                //周围八个格子中墙壁的数量超过一半就变成墙壁，正好一半时保持原样
+                let walls = map.neighbours(x, y, true).filter(|&(nx, ny)| !map.is_floor(nx, ny)).count();
+                let tile = match walls {
+                    5.. => Tile::Wall,
+                    4 => map.get(x, y).unwrap_or(Tile::Wall),
+                    _ => Tile::Floor,
+                };
+                next.set(x, y, tile);
+            }
+        }
+        map = next;
+    }
+
+    //找出最大的一片连通的地板，玩家在其中随机的一个格子出发
+    let mut largest: Vec<(usize, usize)> = Vec::new();
+    let mut seen = vec![false; DUNGEON_WIDTH * DUNGEON_HEIGHT];
+    for (x, y) in map.floor_tiles() {
+        if seen[y * DUNGEON_WIDTH + x] {
+            continue;
+        }
+        let region = map.reachable_from(x, y);
+        for &(rx, ry) in &region {
+            seen[ry * DUNGEON_WIDTH + rx] = true;
+        }
+        if region.len() > largest.len() {
+            largest = region;
+        }
+    }
+
+    //极少数情况下洞穴几乎全是墙壁，这时改用房间风格
+    if largest.len() < DUNGEON_WIDTH * DUNGEON_HEIGHT / 5 {
+        return generate_rooms(rng);
+    }
+    largest.sort();
+    let start = largest[rng.gen_range(0..largest.len())];
+    (map, start)
+}
+
+//两个格子之间的距离（斜着走也算一步）
+fn distance(a: (usize, usize), b: (usize, usize)) -> usize {
+    a.0.abs_diff(b.0).max(a.1.abs_diff(b.1))
+}
+
+//选择怪物出生点：尽量离玩家远一些，至少不能紧挨着玩家
+fn pick_monster_spawns(map: &Map, player_start: (usize, usize), rng: &mut GameRng) -> Vec<(usize, usize)> {
+    let floors = map.floor_tiles();
+    let mut candidates: Vec<(usize, usize)> = floors
+        .iter()
+        .copied()
+        .filter(|&pos| distance(pos, player_start) >= MIN_SPAWN_DISTANCE)
+        .collect();
+    if candidates.len() < SPAWN_COUNT {
+        candidates = floors.into_iter().filter(|&pos| distance(pos, player_start) > 1).collect();
+    }
+
+    let mut spawns = Vec::new();
+    while spawns.len() < SPAWN_COUNT && !candidates.is_empty() {
+        spawns.push(candidates.swap_remove(rng.gen_range(0..candidates.len())));
+    }
+    spawns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    //从出生点上下左右地走，能走到的地板数量
+    fn walkable_count(level: &Level) -> usize {
+        let map = &level.map;
+        let mut seen = vec![false; map.width() * map.height()];
+        let (sx, sy) = level.player_start;
+        seen[sy * map.width() + sx] = true;
+        let mut queue = VecDeque::from([level.player_start]);
+        let mut count = 0;
+        while let Some((x, y)) = queue.pop_front() {
+            count += 1;
+            for (nx, ny) in map.neighbours(x, y, false) {
+                if map.is_floor(nx, ny) && !seen[ny * map.width() + nx] {
+                    seen[ny * map.width() + nx] = true;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn generated_dungeons_keep_their_guarantees() {
+        for style in [DungeonStyle::Rooms, DungeonStyle::Caves] {
+            for seed in 0..200 {
+                let level = generate(style, seed);
+                let floors = level.map.floor_tiles();
+                assert!(level.map.is_floor(level.player_start.0, level.player_start.1));
+                //所有地板都能从出生点走到
+                assert_eq!(walkable_count(&level), floors.len(), "{:?} #{}", style, seed);
+                //怪物出生点在地板上，并且不紧挨着玩家
+                assert!(!level.monster_spawns.is_empty());
+                for &spawn in &level.monster_spawns {
+                    assert!(level.map.is_floor(spawn.0, spawn.1));
+                    assert!(distance(spawn, level.player_start) > 1, "{:?} #{} {:?}", style, seed, spawn);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_dungeon() {
+        let a = generate(DungeonStyle::Caves, 99);
+        let b = generate(DungeonStyle::Caves, 99);
+        assert_eq!(a.map.floor_tiles(), b.map.floor_tiles());
+        assert_eq!(a.monster_spawns, b.monster_spawns);
+    }
+}
@@ -0,0 +1,92 @@
+//动态难度模块：选择 Dynamic 难度时，每隔一段时间根据玩家的表现调整"压力"
+//看三个指标：玩家的生命值、每分钟被攻击的次数、每分钟吃到的血包数量（吃得越多说明越需要回血）
+//压力越大，怪物走得越快、血包刷新得越慢、波次的怪物越多越密；压力始终限制在定义文件给出的范围之内
+use serde::{Deserialize, Serialize};
+use crate::game::Player;
+
+//动态难度的数值
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DynamicDef {
+    //每隔多少秒评估一次玩家的表现
+    pub evaluate_interval: f32,
+    //每次评估之后压力最多变化多少
+    pub step: f32,
+    //压力的范围（1.0 为难度预设本来的数值）
+    pub min_pressure: f32,
+    pub max_pressure: f32,
+    //希望玩家保持的生命值比例（0 到 1）
+    pub target_health: f32,
+    //希望玩家每分钟被攻击的次数
+    pub target_hits_per_minute: f32,
+    //希望玩家每分钟吃到的血包数量
+    pub target_packs_per_minute: f32,
+    //表现与目标相差不超过这个比例时不调整
+    pub tolerance: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DynamicDirector {
+    //当前的压力
+    pub pressure: f32,
+    //距离下一次评估的时间（秒）
+    timer: f32,
+    //上一次评估时玩家被攻击的次数和吃到的血包数量
+    last_attacks: usize,
+    last_packs: usize,
+    config: DynamicDef,
+}
+
+impl DynamicDirector {
+    pub fn new(config: &DynamicDef) -> Self {
+        Self {
+            pressure: 1.0,
+            timer: config.evaluate_interval,
+            last_attacks: 0,
+            last_packs: 0,
+            config: config.clone(),
+        }
+    }
+
+    //玩家现在有多吃力：三个指标分别除以目标值再取平均，1.0 表示正好符合目标，越大越吃力
+    fn stress(&self, player: &Player) -> f32 {
+        let minutes = self.config.evaluate_interval / 60.0;
+        let hits_per_minute = (player.monster_attacks - self.last_attacks) as f32 / minutes;
+        let packs_per_minute = (player.health_packs_collected - self.last_packs) as f32 / minutes;
+        let missing_health = 1.0 - player.health as f32 / player.max_health as f32;
+
+        let health_stress = missing_health / (1.0 - self.config.target_health).max(0.01);
+        let hit_stress = hits_per_minute / self.config.target_hits_per_minute;
+        let pack_stress = packs_per_minute / self.config.target_packs_per_minute;
+        (health_stress + hit_stress + pack_stress) / 3.0
+    }
+
+    //推进计时，到了评估的时间就根据玩家的表现调整压力；压力变化时返回新的压力
+    pub fn update(&mut self, delta: f32, player: &Player) -> Option<f32> {
+        self.timer -= delta;
+        if self.timer > 0.0 {
+            return None;
+        }
+        self.timer = self.config.evaluate_interval;
+
+        let stress = self.stress(player);
+        self.last_attacks = player.monster_attacks;
+        self.last_packs = player.health_packs_collected;
+
+        let target = if stress > 1.0 + self.config.tolerance {
+            //玩家很吃力：减小压力
+            self.pressure - self.config.step
+        } else if stress < 1.0 - self.config.tolerance {
+            //玩家很轻松：加大压力
+            self.pressure + self.config.step
+        } else {
+            return None;
+        };
+        let pressure = target.clamp(self.config.min_pressure, self.config.max_pressure);
+        if pressure == self.pressure {
+            return None;
+        }
+        self.pressure = pressure;
+        Some(pressure)
+    }
+}
@@ -0,0 +1,435 @@
+//视野模块：用"对称阴影投射"（symmetric shadowcasting）算法计算从某个格子出发能看见哪些格子
+//墙壁会挡住视线（墙壁本身可以被看见）。这个算法是对称的：A 能看见 B 当且仅当 B 能看见 A，
+//所以只需要算一次玩家的视野，怪物和渲染器都可以直接查询
+use crate::map::Map;
+use serde::{Deserialize, Serialize};
+
+//一个格子出发的视野结果
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "FieldOfViewData")]
+pub struct FieldOfView {
+    width: usize,
+    height: usize,
+    visible: Vec<bool>,
+}
+
+//存档中的原始视野数据，检查格子的数量之后才能变成 FieldOfView
+#[derive(Deserialize)]
+struct FieldOfViewData {
+    width: usize,
+    height: usize,
+    visible: Vec<bool>,
+}
+
+impl TryFrom<FieldOfViewData> for FieldOfView {
+    type Error = String;
+
+    fn try_from(data: FieldOfViewData) -> Result<Self, String> {
+        if data.visible.len() != data.width * data.height {
+            return Err(format!("视野应有 {}x{} 个格子，实际有 {} 个", data.width, data.height, data.visible.len()));
+        }
+        Ok(Self {
+            width: data.width,
+            height: data.height,
+            visible: data.visible,
+        })
+    }
+}
+
+impl FieldOfView {
+    //视野的大小是否和地图一样
+    pub fn fits(&self, map: &Map) -> bool {
+        (self.width, self.height) == (map.width(), map.height())
+    }
+
+    //(x, y) 是否可见（越界视为不可见）
+    pub fn is_visible(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height && self.visible[y * self.width + x]
+    }
+
+    fn mark(&mut self, x: usize, y: usize) {
+        self.visible[y * self.width + x] = true;
+    }
+}
+
+//玩家去过的地方：曾经出现在视野中的格子都会被记住（战争迷雾）
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "ExploredData")]
+pub struct Explored {
+    width: usize,
+    explored: Vec<bool>,
+}
+
+//存档中的原始数据，检查格子的数量之后才能变成 Explored
+#[derive(Deserialize)]
+struct ExploredData {
+    width: usize,
+    explored: Vec<bool>,
+}
+
+impl TryFrom<ExploredData> for Explored {
+    type Error = String;
+
+    fn try_from(data: ExploredData) -> Result<Self, String> {
+        if data.width == 0 || !data.explored.len().is_multiple_of(data.width) {
+            return Err(format!("去过的格子有 {} 个，不是宽度 {} 的整数倍", data.explored.len(), data.width));
+        }
+        Ok(Self {
+            width: data.width,
+            explored: data.explored,
+        })
+    }
+}
+
+impl Explored {
+    //记录的大小是否和地图一样
+    pub fn fits(&self, map: &Map) -> bool {
+        self.width == map.width() && self.explored.len() == map.width() * map.height()
+    }
+
+    //一开始什么都没见过
+    pub fn new(map: &Map) -> Self {
+        Self {
+            width: map.width(),
+            explored: vec![false; map.width() * map.height()],
+        }
+    }
+
+    //把当前视野中的格子全部记下来
+    pub fn remember(&mut self, fov: &FieldOfView) {
+        for (explored, &visible) in self.explored.iter_mut().zip(&fov.visible) {
+            *explored |= visible;
+        }
+    }
+
+    //(x, y) 是否曾经被看见过（越界视为没有）
+    pub fn is_explored(&self, x: usize, y: usize) -> bool {
+        x < self.width && self.explored.get(y * self.width + x).copied().unwrap_or(false)
+    }
+}
+
+//斜率，用分数表示以避免浮点误差：num / den（den 总是正数）
+#[derive(Clone, Copy)]
+struct Slope {
+    num: i64,
+    den: i64,
+}
+
+//阴影投射中的一行：depth 为离起点的距离，只扫描 [start, end] 两个斜率之间的格子
+#[derive(Clone, Copy)]
+struct Row {
+    depth: i64,
+    start: Slope,
+    end: Slope,
+}
+
+impl Row {
+    //这一行需要扫描的列的范围
+    fn columns(&self) -> std::ops::RangeInclusive<i64> {
+        //depth * start 四舍五入（0.5 向上取），depth * end 四舍五入（0.5 向下取）
+        let min = (2 * self.depth * self.start.num + self.start.den).div_euclid(2 * self.start.den);
+        let max = -(self.end.den - 2 * self.depth * self.end.num).div_euclid(2 * self.end.den);
+        min..=max
+    }
+
+    fn next(&self) -> Row {
+        Row {
+            depth: self.depth + 1,
+            ..*self
+        }
+    }
+
+    //格子的中心是否在这一行的扫描范围之内（保证视野对称）
+    fn is_symmetric(&self, col: i64) -> bool {
+        col * self.start.den >= self.depth * self.start.num && col * self.end.den <= self.depth * self.end.num
+    }
+}
+
+//格子左边缘所在的斜率
+fn slope(depth: i64, col: i64) -> Slope {
+    Slope {
+        num: 2 * col - 1,
+        den: 2 * depth,
+    }
+}
+
+//计算从 origin 出发、半径 radius 以内的视野
+pub fn compute_fov(map: &Map, origin: (usize, usize), radius: usize) -> FieldOfView {
+    let mut fov = FieldOfView {
+        width: map.width(),
+        height: map.height(),
+        visible: vec![false; map.width() * map.height()],
+    };
+    if map.get(origin.0, origin.1).is_none() {
+        return fov;
+    }
+    fov.mark(origin.0, origin.1);
+
+    //把视野分成上下左右四个象限分别扫描
+    for quadrant in 0..4 {
+        let scanner = Scanner {
+            map,
+            origin: (origin.0 as i64, origin.1 as i64),
+            quadrant,
+            radius: radius as i64,
+        };
+        let first_row = Row {
+            depth: 1,
+            start: Slope { num: -1, den: 1 },
+            end: Slope { num: 1, den: 1 },
+        };
+        scanner.scan(first_row, &mut fov);
+    }
+    fov
+}
+
+//在一个象限内扫描视野
+struct Scanner<'a> {
+    map: &'a Map,
+    origin: (i64, i64),
+    quadrant: u8,
+    radius: i64,
+}
+
+impl Scanner<'_> {
+    //象限内的 (depth, col) 转换成地图坐标
+    fn transform(&self, depth: i64, col: i64) -> (i64, i64) {
+        let (ox, oy) = self.origin;
+        match self.quadrant {
+            0 => (ox + col, oy - depth), //上
+            1 => (ox + depth, oy + col), //右
+            2 => (ox + col, oy + depth), //下
+            _ => (ox - depth, oy + col), //左
+        }
+    }
+
+    //格子是否挡住视线（地图外也当作墙壁）
+    fn is_wall(&self, depth: i64, col: i64) -> bool {
+        let (x, y) = self.transform(depth, col);
+        !self.map.in_bounds(x as isize, y as isize) || !self.map.is_floor(x as usize, y as usize)
+    }
+
+    //在视野半径之内并且在地图上的格子才会被标记为可见
+    fn reveal(&self, depth: i64, col: i64, fov: &mut FieldOfView) {
+        let (x, y) = self.transform(depth, col);
+        if depth * depth + col * col <= self.radius * self.radius && self.map.in_bounds(x as isize, y as isize) {
+            fov.mark(x as usize, y as usize);
+        }
+    }
+
+    fn scan(&self, mut row: Row, fov: &mut FieldOfView) {
+        if row.depth > self.radius {
+            return;
+        }
+
+        //上一个格子是否是墙壁（None 表示这是这一行的第一个格子）
+        let mut prev_wall: Option<bool> = None;
+        for col in row.columns() {
+            let wall = self.is_wall(row.depth, col);
+            if wall || row.is_symmetric(col) {
+                self.reveal(row.depth, col, fov);
+            }
+            //从墙壁进入地板：视野的起始边缘收窄到这个格子
+            if prev_wall == Some(true) && !wall {
+                row.start = slope(row.depth, col);
+            }
+            //从地板进入墙壁：墙壁之前的部分继续往下一行扫描
+            if prev_wall == Some(false) && wall {
+                let mut next_row = row.next();
+                next_row.end = slope(row.depth, col);
+                self.scan(next_row, fov);
+            }
+            prev_wall = Some(wall);
+        }
+        if prev_wall == Some(false) {
+            self.scan(row.next(), fov);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::Tile;
+
+    //把手绘的地图转换成 Map：'#' 为墙壁，'.' 为地板，'@' 为观察者（站在地板上）
+    fn parse_fixture(rows: &[&str]) -> (Map, (usize, usize)) {
+        let mut map = Map::new(rows[0].len(), rows.len(), Tile::Floor);
+        let mut origin = (0, 0);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                match c {
+                    '#' => map.set(x, y, Tile::Wall),
+                    '@' => origin = (x, y),
+                    _ => {}
+                }
+            }
+        }
+        (map, origin)
+    }
+
+    //把视野画成同样大小的图：'*' 为可见，'-' 为不可见
+    fn draw(fov: &FieldOfView, map: &Map) -> Vec<String> {
+        (0..map.height())
+            .map(|y| (0..map.width()).map(|x| if fov.is_visible(x, y) { '*' } else { '-' }).collect())
+            .collect()
+    }
+
+    fn check(rows: &[&str], radius: usize, expected: &[&str]) {
+        let (map, origin) = parse_fixture(rows);
+        let actual = draw(&compute_fov(&map, origin, radius), &map);
+        assert_eq!(actual, expected, "\n地图:\n{}\n实际视野:\n{}\n", rows.join("\n"), actual.join("\n"));
+    }
+
+    #[test]
+    fn open_room_is_fully_visible() {
+        check(
+            &[
+                "#######",
+                "#.....#",
+                "#..@..#",
+                "#.....#",
+                "#######",
+            ],
+            10,
+            &[
+                "*******",
+                "*******",
+                "*******",
+                "*******",
+                "*******",
+            ],
+        );
+    }
+
+    #[test]
+    fn pillar_casts_a_shadow() {
+        check(
+            &[
+                "#########",
+                "#.......#",
+                "#.@.#...#",
+                "#.......#",
+                "#########",
+            ],
+            10,
+            &[
+                "*********",
+                "*******--",
+                "*****----",
+                "*******--",
+                "*********",
+            ],
+        );
+    }
+
+    #[test]
+    fn wall_hides_the_next_room() {
+        check(
+            &[
+                "#########",
+                "#...#...#",
+                "#.@.#...#",
+                "#...#...#",
+                "#########",
+            ],
+            10,
+            &[
+                "*****----",
+                "*****----",
+                "*****----",
+                "*****----",
+                "*****----",
+            ],
+        );
+    }
+
+    #[test]
+    fn doorway_shows_a_cone() {
+        check(
+            &[
+                "###########",
+                "#.........#",
+                "#.........#",
+                "#.........#",
+                "####.######",
+                "#...@.....#",
+                "###########",
+            ],
+            20,
+            &[
+                "--*****----",
+                "--*****----",
+                "---***-----",
+                "---***-----",
+                "***********",
+                "***********",
+                "***********",
+            ],
+        );
+    }
+
+    #[test]
+    fn corridor_around_a_corner_is_hidden() {
+        check(
+            &[
+                "#######",
+                "#@....#",
+                "#####.#",
+                "#####.#",
+                "#.....#",
+                "#######",
+            ],
+            10,
+            &[
+                "*******",
+                "*******",
+                "*****-*",
+                "-------",
+                "-------",
+                "-------",
+            ],
+        );
+    }
+
+    #[test]
+    fn radius_limits_the_view() {
+        check(
+            &[
+                "#..........",
+                "#@.........",
+                "#..........",
+            ],
+            3,
+            &[
+                "****-------",
+                "*****------",
+                "****-------",
+            ],
+        );
+    }
+
+    #[test]
+    fn fov_is_symmetric() {
+        let (map, _) = parse_fixture(&[
+            "############",
+            "#....#.....#",
+            "#.##.#.###.#",
+            "#.#......#.#",
+            "#.#.####.#.#",
+            "#...#..#...#",
+            "###.#..#.###",
+            "#.........##",
+            "#..##..#...#",
+            "############",
+        ]);
+        let floors = map.floor_tiles();
+        for &a in &floors {
+            let fov = compute_fov(&map, a, 20);
+            for &b in &floors {
+                let back = compute_fov(&map, b, 20);
+                assert_eq!(fov.is_visible(b.0, b.1), back.is_visible(a.0, a.1), "{:?} 与 {:?} 的视野不对称", a, b);
+            }
+        }
+    }
+}
@@ -0,0 +1,676 @@
+//游戏规则的核心模块：这里只负责"模拟"，不加载图片、不读键盘、不绘制任何东西
+//因此这一部分可以在没有窗口的环境中运行（例如在CI上跑单元测试）
+use macroquad::color::{Color, GREEN, ORANGE, RED, YELLOW};
+use ::rand::SeedableRng;
+use crate::rng::GameRng;
+use serde::{Deserialize, Serialize};
+use crate::monster::{HitOutcome, Monsters};
+use crate::health_pack::HealthPacks;
+use crate::projectile::Projectiles;
+use crate::boss::BossEncounter;
+use crate::waves::{WaveDirector, WaveEvent};
+use crate::dynamic::DynamicDirector;
+use crate::scoring::ScoreBreakdown;
+use crate::monster_kind::MonsterKind;
+use crate::menu::GameDifficulty;
+use crate::level::Level;
+use crate::map::Map;
+use crate::definitions::Definitions;
+use crate::fov::{compute_fov, Explored, FieldOfView};
+
+//玩家的视野半径（格子数），应不小于所有怪物的视野半径（视野是对称的，怪物用它判断能否看见玩家）
+pub const VIEW_RADIUS: usize = 10;
+//攻击动作显示的时间（秒）
+pub const SWING_TIME: f32 = 0.15;
+
+//玩家结构体
+#[derive(Serialize, Deserialize)]
+pub struct Player {
+    pub x: usize,
+    pub y: usize,
+    //上一步模拟时玩家的位置，渲染时在两者之间插值
+    pub prev_x: usize,
+    pub prev_y: usize,
+    pub health: i32,
+    //最大生命值
+    pub max_health: i32,
+    pub health_packs_collected: usize,
+    pub monster_attacks: usize,
+    //强化状态剩余的时间（秒）
+    pub power_up_time: f32,
+    //玩家面朝的方向（最后一次按下的方向键），攻击会打中这个方向上相邻的格子
+    pub facing: (isize, isize),
+    //距离下一次可以攻击还剩的时间（秒）
+    pub attack_cooldown: f32,
+    //攻击动作还要显示的时间（秒），只用于绘制
+    pub swing_time: f32,
+    //打死的怪物数量
+    pub kills: usize,
+}
+
+//玩家结构体的方法
+impl Player {
+    //玩家是否处于强化状态
+    pub fn has_power_up(&self) -> bool {
+        self.power_up_time > 0.0
+    }
+
+    //玩家受到怪物攻击的方法
+    pub fn take_damage(&mut self, amount: i32) {
+        self.health -= amount;
+        self.monster_attacks += 1;
+        if self.health < 0 {
+            self.health = 0;
+        }
+    }
+
+    //玩家吃血包回血的方法
+    pub fn heal(&mut self, amount: i32) {
+        self.health += amount;
+        self.health_packs_collected += 1;
+        if self.health > self.max_health {
+            self.health = self.max_health;
+        }
+    }
+}
+
+//一帧的输入快照：由渲染层（键盘、鼠标）收集后交给模拟层
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Input {
+    pub left: bool,
+    pub right: bool,
+    pub up: bool,
+    pub down: bool,
+    //空格键：拾取脚下的血包
+    pub pick_up: bool,
+    //X 键：攻击面前的格子
+    pub attack: bool,
+    //点击了暂停按钮
+    pub toggle_pause: bool,
+}
+
+impl Input {
+    //把另一帧的输入合并进来（任意一帧按下过就算按下），
+    //这样在两次模拟之间按下的键不会丢失
+    pub fn merge(&mut self, other: Input) {
+        self.left |= other.left;
+        self.right |= other.right;
+        self.up |= other.up;
+        self.down |= other.down;
+        self.pick_up |= other.pick_up;
+        self.attack |= other.attack;
+        self.toggle_pause |= other.toggle_pause;
+    }
+}
+
+//一局游戏结束时的统计信息
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RunStats {
+    //这一局使用的随机种子，用同一个种子可以重现这一局
+    pub seed: u64,
+    pub game_time: f32,
+    pub health_packs_collected: usize,
+    pub monster_attacks: usize,
+    pub kills: usize,
+    //是否打败了首领
+    pub boss_defeated: bool,
+    //到达的最高波次
+    pub best_wave: usize,
+    //这一局的难度和分数
+    pub difficulty: GameDifficulty,
+    pub score: ScoreBreakdown,
+}
+
+//模拟层在一次更新中产生的事件，渲染层可以据此播放提示、音效等
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameEvent {
+    PlayerMoved { x: usize, y: usize },
+    PlayerAttacked { damage: i32 },
+    PlayerHealed { amount: i32 },
+    MonsterHit { x: usize, y: usize, damage: i32 },
+    MonsterKilled { x: usize, y: usize },
+    BossAppeared,
+    BossPhaseChanged { phase: usize },
+    BossDefeated,
+    WaveIncoming { wave: usize },
+    WaveSpawned { wave: usize },
+    HealthAlreadyFull,
+    Paused,
+    Resumed,
+    GameOver(RunStats),
+}
+
+//定义游戏结构体
+#[derive(Serialize, Deserialize)]
+pub struct Game {
+    pub map: Map, // 地图
+    pub fov: FieldOfView, // 玩家当前能看见的格子
+    pub explored: Explored, // 玩家曾经看见过的格子
+    pub player: Player, // 玩家对象
+    pub monsters: Monsters, // 怪物集合
+    pub health_packs: HealthPacks, // 血包集合
+    pub projectiles: Projectiles, // 正在飞行的弹丸
+    pub boss: BossEncounter, // 首领的出场和阶段
+    pub waves: WaveDirector, // 怪物的波次
+    pub dynamic: Option<DynamicDirector>, // 动态难度（只有选择 Dynamic 难度时才有）
+    #[serde(skip)]
+    pub message: Option<(String, Color)>, // 游戏中的提示消息（不写进存档）
+    #[serde(skip)]
+    message_timer: f32, // 消息显示的计时器
+    damage_cooldown: f32, // 玩家受到伤害后的冷却时间
+    pub game_time: f32, // 游戏进行的时间
+    pub paused: bool, // 游戏是否暂停
+    pub seed: u64, // 这一局的随机种子
+    pub difficulty: GameDifficulty, // 这一局的难度
+    definitions: Definitions, // 这一局使用的数值定义
+    rng: GameRng, // 由种子生成的随机数生成器，怪物和血包的所有随机行为都用它
+}
+
+//游戏结构体的相关方法
+impl Game {
+    //创建游戏实例的方法（不需要窗口，也不加载任何资源）
+    //同样的难度、种子、关卡和数值定义总是得到同样的一局游戏
+    pub fn new(difficulty: GameDifficulty, seed: u64, level: &Level, definitions: &Definitions) -> Self {
+        let mut rng = GameRng::seed_from_u64(seed);
+
+        //初始化玩家对象，放在关卡的出生点上
+        let (start_x, start_y) = level.player_start;
+        let player = Player {
+            x: start_x,
+            y: start_y,
+            prev_x: start_x,
+            prev_y: start_y,
+            health: definitions.player.max_health,
+            max_health: definitions.player.max_health,
+            health_packs_collected: 0,
+            monster_attacks: 0,
+            power_up_time: 0.0,
+            facing: (0, 1),
+            attack_cooldown: 0.0,
+            swing_time: 0.0,
+            kills: 0,
+        };
+
+        let fov = compute_fov(&level.map, level.player_start, VIEW_RADIUS);
+        let mut explored = Explored::new(&level.map);
+        explored.remember(&fov);
+
+        //返回游戏的实例
+        Self {
+            map: level.map.clone(),
+            fov,
+            explored,
+            player,
+            //根据游戏难度的预设生成怪物（种类搭配、视野半径和移动方式）
+            monsters: Monsters::new(definitions.difficulty(difficulty), definitions, level, &mut rng),
+            projectiles: Projectiles::default(),
+            boss: BossEncounter::new(level),
+            waves: WaveDirector::new(level, &definitions.difficulty(difficulty).waves),
+            dynamic: (difficulty == GameDifficulty::Dynamic).then(|| DynamicDirector::new(&definitions.dynamic)),
+            health_packs: HealthPacks::new(level, definitions.pickups.health_pack.spawn_interval),
+            message: None,
+            message_timer: 0.0,
+            damage_cooldown: 0.0,
+            game_time: 0.0,
+            paused: false,
+            seed,
+            difficulty,
+            definitions: definitions.clone(),
+            rng,
+        }
+    }
+
+    //首领还活着时返回它的 (生命值, 最大生命值)，用来绘制首领的血条
+    pub fn boss_health(&self) -> Option<(i32, i32)> {
+        self.monsters
+            .list
+            .iter()
+            .find(|m| m.kind == MonsterKind::Boss)
+            .map(|boss| (boss.health, boss.stats.health))
+    }
+
+    //当前这一局的统计信息
+    pub fn stats(&self) -> RunStats {
+        RunStats {
+            seed: self.seed,
+            game_time: self.game_time,
+            health_packs_collected: self.player.health_packs_collected,
+            monster_attacks: self.player.monster_attacks,
+            kills: self.player.kills,
+            boss_defeated: self.boss.is_defeated(),
+            best_wave: self.waves.reached(),
+            difficulty: self.difficulty,
+            score: ScoreBreakdown::new(
+                &self.definitions.scoring,
+                self.difficulty,
+                self.game_time,
+                self.player.health_packs_collected,
+                self.player.monster_attacks,
+                self.player.kills,
+            ),
+        }
+    }
+
+    //玩家尝试移动一格（必须移动到地板，不可以移动到墙壁），撞墙时也会转向
+    fn try_move_player(&mut self, dx: isize, dy: isize, events: &mut Vec<GameEvent>) {
+        self.player.facing = (dx, dy);
+        let Some((new_x, new_y)) = self.map.neighbour(self.player.x, self.player.y, dx, dy) else {
+            return;
+        };
+        if self.map.is_floor(new_x, new_y) {
+            self.player.x = new_x;
+            self.player.y = new_y;
+            events.push(GameEvent::PlayerMoved { x: new_x, y: new_y });
+        }
+    }
+
+    //玩家攻击面前的格子：打中怪物时扣除怪物的生命值并把它击退，打死时计入击杀数
+    fn player_attack(&mut self, events: &mut Vec<GameEvent>) {
+        let player = &self.definitions.player;
+        self.player.attack_cooldown = player.attack_cooldown;
+        self.player.swing_time = SWING_TIME;
+
+        let (dx, dy) = self.player.facing;
+        let Some((x, y)) = self.map.neighbour(self.player.x, self.player.y, dx, dy) else {
+            return;
+        };
+        match self.monsters.hit(&self.map, (x, y), (dx, dy), player.attack_damage, player.knockback) {
+            Some(HitOutcome::Hurt) => {
+                events.push(GameEvent::MonsterHit { x, y, damage: player.attack_damage });
+            }
+            Some(HitOutcome::Killed(kind)) => {
+                self.player.kills += 1;
+                if kind == MonsterKind::Boss {
+                    self.boss.defeat();
+                    self.message = Some(("Boss defeated!".to_string(), YELLOW));
+                    self.message_timer = 3.0;
+                    events.push(GameEvent::BossDefeated);
+                } else {
+                    self.message = Some(("Monster slain!".to_string(), YELLOW));
+                    self.message_timer = 1.0;
+                }
+                events.push(GameEvent::MonsterKilled { x, y });
+            }
+            None => {}
+        }
+    }
+
+    //更新游戏状态的函数（游戏的本质就是一个状态机）
+    //输入快照和时间步长由外部传入，返回这一次更新中发生的事件
+    pub fn update(&mut self, input: &Input, delta: f32) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+
+        //记录这一步开始前的位置，供渲染插值使用
+        self.player.prev_x = self.player.x;
+        self.player.prev_y = self.player.y;
+        for monster in &mut self.monsters.list {
+            monster.prev_x = monster.x;
+            monster.prev_y = monster.y;
+        }
+
+        //点击暂停按钮切换暂停状态
+        if input.toggle_pause {
+            self.paused = !self.paused;
+            events.push(if self.paused { GameEvent::Paused } else { GameEvent::Resumed });
+        }
+
+        //游戏如果暂停，则状态不更新
+        if self.paused {
+            return events;
+        }
+
+        //在本游戏里，玩家生存的时间是衡量玩家游戏情况的硬指标，因此需要计时
+        self.game_time += delta;
+
+        // 如果玩家生命值为 0，返回游戏结束事件（包含游戏时间、收集的血包数量和被攻击次数）
+        if self.player.health <= 0 {
+            events.push(GameEvent::GameOver(self.stats()));
+            return events;
+        }
+
+        if self.damage_cooldown > 0.0 {
+            self.damage_cooldown -= delta;
+        }
+        if self.player.power_up_time > 0.0 {
+            self.player.power_up_time -= delta;
+        }
+        if self.player.attack_cooldown > 0.0 {
+            self.player.attack_cooldown -= delta;
+        }
+        if self.player.swing_time > 0.0 {
+            self.player.swing_time -= delta;
+        }
+
+        //玩家上下左右四个方向的移动
+        if input.left {
+            self.try_move_player(-1, 0, &mut events);
+        }
+        if input.right {
+            self.try_move_player(1, 0, &mut events);
+        }
+        if input.up {
+            self.try_move_player(0, -1, &mut events);
+        }
+        if input.down {
+            self.try_move_player(0, 1, &mut events);
+        }
+
+        //攻击面前的格子（先移动再攻击，攻击的是转向之后面对的格子）
+        if input.attack && self.player.attack_cooldown <= 0.0 {
+            self.player_attack(&mut events);
+        }
+
+        let player_pos = (self.player.x, self.player.y);
+        //玩家移动之后重新计算视野
+        self.fov = compute_fov(&self.map, player_pos, VIEW_RADIUS);
+        self.explored.remember(&self.fov);
+        let powered_up = self.player.has_power_up();
+        let shots = self.monsters.update(delta, &self.map, &self.fov, player_pos, powered_up, &mut self.rng);
+
+        //弹丸往前飞，新射出的弹丸从下一步开始飞；打中玩家的弹丸直接造成伤害（不受近身攻击的冷却时间限制）
+        self.projectiles.update(delta, &self.map);
+        for shot in shots {
+            self.projectiles.spawn(shot);
+        }
+        let player_prev = (self.player.prev_x, self.player.prev_y);
+        let shot_damage = self.projectiles.take_hits(player_pos, player_prev);
+        if shot_damage > 0 {
+            self.player.take_damage(shot_damage);
+            self.message = Some((format!("Shot! -{}HP", shot_damage), RED));
+            self.message_timer = 1.0;
+            events.push(GameEvent::PlayerAttacked { damage: shot_damage });
+        }
+
+        //首领到时间就出场，出场之后按生命值切换阶段
+        if self.boss.should_appear(self.game_time, &self.definitions)
+            && self.boss.appear(&self.definitions, &mut self.monsters, player_pos, &mut self.rng)
+        {
+            self.message = Some(("The boss has arrived!".to_string(), RED));
+            self.message_timer = 2.0;
+            events.push(GameEvent::BossAppeared);
+        }
+        let boss_phase = self.boss.update(
+            delta,
+            &self.definitions,
+            &self.map,
+            &mut self.monsters,
+            &mut self.projectiles,
+            player_pos,
+        );
+        if let Some(phase) = boss_phase {
+            self.message = Some(("The boss grows furious!".to_string(), RED));
+            self.message_timer = 2.0;
+            events.push(GameEvent::BossPhaseChanged { phase });
+        }
+
+        //按波次刷出新的怪物，刷怪之前先预警
+        match self.waves.update(delta, &self.definitions, &mut self.monsters, player_pos, &mut self.rng) {
+            Some(WaveEvent::Incoming(wave)) => {
+                self.message = Some((format!("Wave {} incoming!", wave), ORANGE));
+                self.message_timer = 1.5;
+                events.push(GameEvent::WaveIncoming { wave });
+            }
+            Some(WaveEvent::Spawned(wave)) => events.push(GameEvent::WaveSpawned { wave }),
+            None => {}
+        }
+
+        //动态难度：根据玩家的表现调整怪物的速度、血包的刷新间隔和刷怪的压力
+        if let Some(director) = &mut self.dynamic
+            && let Some(pressure) = director.update(delta, &self.player)
+        {
+            self.monsters.speed_scale = pressure;
+            self.health_packs.set_spawn_interval(self.definitions.pickups.health_pack.spawn_interval * pressure);
+            self.waves.pressure = pressure;
+        }
+
+        self.health_packs.update(delta, &mut self.rng);
+
+        if self.damage_cooldown <= 0.0 {
+            for monster in &self.monsters.list {
+                let (mx, my) = monster.tile();
+
+                // 如果玩家和怪物重叠，玩家受到伤害并设置冷却时间和提示消息
+                if mx == self.player.x && my == self.player.y {
+                    //不同种类的怪物伤害不同
+                    let damage = monster.stats.damage;
+                    self.player.take_damage(damage);
+                    self.damage_cooldown = self.definitions.player.damage_cooldown;
+                    self.message = Some((format!("Attacked! -{}HP", damage), RED));
+                    self.message_timer = 1.0;
+                    events.push(GameEvent::PlayerAttacked { damage });
+                    break;
+                }
+            }
+        }
+
+        if input.pick_up
+            && let Some(idx) = self.health_packs.list.iter().position(|hp| {
+                hp.x == self.player.x && hp.y == self.player.y
+            })
+        {
+            //玩家生命值满了，输出提示信息提示玩家
+            if self.player.health == self.player.max_health {
+                self.message = Some(("Energy is already full!".to_string(), GREEN));
+                self.message_timer = 2.0;
+                events.push(GameEvent::HealthAlreadyFull);
+            } else {
+                //玩家生命值未满并且玩家与血包重叠，玩家回血并获得短暂的强化，并将血包移除
+                let pack = &self.definitions.pickups.health_pack;
+                self.player.heal(pack.heal);
+                self.player.power_up_time = pack.power_up_time;
+                self.health_packs.list.remove(idx);
+                events.push(GameEvent::PlayerHealed { amount: pack.heal });
+            }
+        }
+
+        if self.message.is_some() {
+            self.message_timer -= delta;
+            if self.message_timer <= 0.0 {
+                self.message = None;
+                self.message_timer = 0.0;//如果消息显示时间到，则该信息需要移除
+            }
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::health_pack::HealthPack;
+    use crate::monster::Monster;
+
+    const STEP: f32 = 1.0 / 60.0;
+
+    //默认场地上只有玩家的一局：怪物和血包都清空，需要的时候由测试自己放
+    fn empty_game() -> Game {
+        let mut game = Game::new(GameDifficulty::Easy, 1, &Level::default_arena(), &Definitions::builtin());
+        game.monsters.list.clear();
+        game.health_packs.list.clear();
+        game
+    }
+
+    //在 position 上放一个不会移动、不会射击的小怪
+    fn place_monster(game: &mut Game, position: (usize, usize)) {
+        let kind = MonsterKind::Grunt;
+        let mut monster = Monster::new(kind, game.definitions.monster(kind).clone(), position);
+        monster.stats.move_interval = f32::MAX;
+        monster.stats.ranged = None;
+        game.monsters.list.push(monster);
+    }
+
+    //在玩家的格子上放一个不会移动的怪物，返回它的伤害
+    fn monster_on_player(game: &mut Game) -> i32 {
+        place_monster(game, (game.player.x, game.player.y));
+        game.monsters.list.last().unwrap().stats.damage
+    }
+
+    //玩家站在 (x, 3) 上朝右攻击一次
+    fn attack_right_from(game: &mut Game, x: usize) -> Vec<GameEvent> {
+        game.player.x = x;
+        game.player.y = 3;
+        game.player.facing = (1, 0);
+        game.update(&Input { attack: true, ..Input::default() }, STEP)
+    }
+
+    fn monster_tiles(game: &Game) -> Vec<(usize, usize)> {
+        game.monsters.list.iter().map(|m| m.tile()).collect()
+    }
+
+    #[test]
+    fn moving_into_a_wall_is_blocked() {
+        let mut game = empty_game();
+        game.player.x = 1;
+        game.player.y = 1;
+        let events = game.update(&Input { left: true, ..Input::default() }, STEP);
+        assert_eq!((game.player.x, game.player.y), (1, 1));
+        //撞墙时不移动，但会转向
+        assert_eq!(game.player.facing, (-1, 0));
+        assert!(!events.iter().any(|e| matches!(e, GameEvent::PlayerMoved { .. })));
+
+        let events = game.update(&Input { right: true, ..Input::default() }, STEP);
+        assert_eq!((game.player.x, game.player.y), (2, 1));
+        assert!(events.contains(&GameEvent::PlayerMoved { x: 2, y: 1 }));
+    }
+
+    #[test]
+    fn damage_has_a_cooldown() {
+        let mut game = empty_game();
+        let damage = monster_on_player(&mut game);
+        let max_health = game.player.max_health;
+
+        let events = game.update(&Input::default(), STEP);
+        assert!(events.contains(&GameEvent::PlayerAttacked { damage }));
+        assert_eq!(game.player.health, max_health - damage);
+
+        //冷却时间内不会再受到伤害
+        game.update(&Input::default(), STEP);
+        assert_eq!(game.player.health, max_health - damage);
+        assert_eq!(game.player.monster_attacks, 1);
+
+        //冷却时间过去之后又会受到伤害
+        game.update(&Input::default(), game.definitions.player.damage_cooldown);
+        game.update(&Input::default(), STEP);
+        assert_eq!(game.player.health, max_health - 2 * damage);
+        assert_eq!(game.player.monster_attacks, 2);
+    }
+
+    #[test]
+    fn healing_is_capped_at_max_health() {
+        let mut game = empty_game();
+        let pick_up = Input { pick_up: true, ..Input::default() };
+        let (x, y) = (game.player.x, game.player.y);
+        game.player.health = game.player.max_health - 1;
+        game.health_packs.list.push(HealthPack { x, y });
+
+        let events = game.update(&pick_up, STEP);
+        let heal = game.definitions.pickups.health_pack.heal;
+        assert!(events.contains(&GameEvent::PlayerHealed { amount: heal }));
+        assert_eq!(game.player.health, game.player.max_health);
+        assert!(game.health_packs.list.is_empty());
+
+        //生命值已满时不会吃掉血包
+        game.health_packs.list.push(HealthPack { x, y });
+        let events = game.update(&pick_up, STEP);
+        assert!(events.contains(&GameEvent::HealthAlreadyFull));
+        assert_eq!(game.health_packs.list.len(), 1);
+        assert_eq!(game.player.health_packs_collected, 1);
+    }
+
+    #[test]
+    fn attack_hurts_and_knocks_back() {
+        let mut game = empty_game();
+        place_monster(&mut game, (4, 3));
+        let damage = game.definitions.player.attack_damage;
+        let health = game.monsters.list[0].health;
+
+        let events = attack_right_from(&mut game, 3);
+        assert!(events.contains(&GameEvent::MonsterHit { x: 4, y: 3, damage }));
+        assert_eq!(game.monsters.list[0].health, health - damage);
+        assert_eq!(monster_tiles(&game), [(5, 3)]);
+
+        //冷却时间内再按攻击键不会打中
+        game.player.x = 4;
+        let events = game.update(&Input { attack: true, ..Input::default() }, STEP);
+        assert!(!events.iter().any(|e| matches!(e, GameEvent::MonsterHit { .. })));
+        assert_eq!(game.monsters.list[0].health, health - damage);
+    }
+
+    #[test]
+    fn knockback_stops_at_walls_and_other_monsters() {
+        //右边就是墙壁
+        let mut game = empty_game();
+        let right_wall = game.map.width() - 1;
+        place_monster(&mut game, (right_wall - 1, 3));
+        attack_right_from(&mut game, right_wall - 2);
+        assert_eq!(monster_tiles(&game), [(right_wall - 1, 3)]);
+
+        //后面站着另一个怪物
+        let mut game = empty_game();
+        place_monster(&mut game, (4, 3));
+        place_monster(&mut game, (5, 3));
+        attack_right_from(&mut game, 3);
+        assert_eq!(monster_tiles(&game), [(4, 3), (5, 3)]);
+    }
+
+    #[test]
+    fn killed_monsters_are_removed_and_counted() {
+        let mut game = empty_game();
+        place_monster(&mut game, (4, 3));
+        game.monsters.list[0].health = game.definitions.player.attack_damage;
+
+        let events = attack_right_from(&mut game, 3);
+        assert!(events.contains(&GameEvent::MonsterKilled { x: 4, y: 3 }));
+        assert!(game.monsters.list.is_empty());
+        assert_eq!(game.player.kills, 1);
+
+        game.player.health = 0;
+        let events = game.update(&Input::default(), STEP);
+        let Some(GameEvent::GameOver(stats)) = events.last() else {
+            panic!("生命值为 0 之后应该产生 GameOver 事件");
+        };
+        assert_eq!(stats.kills, 1);
+    }
+
+    #[test]
+    fn boss_waits_until_there_is_room() {
+        let mut game = empty_game();
+        game.game_time = game.definitions.boss.spawn_time;
+        //除了玩家脚下，所有地板都站着不会动的怪物
+        let player = (game.player.x, game.player.y);
+        let mut stats = game.definitions.monster(MonsterKind::Grunt).clone();
+        stats.move_interval = f32::MAX;
+        for tile in game.map.floor_tiles().into_iter().filter(|&tile| tile != player) {
+            game.monsters.spawn(MonsterKind::Grunt, stats.clone(), tile);
+        }
+
+        let events = game.update(&Input::default(), STEP);
+        assert!(!events.contains(&GameEvent::BossAppeared));
+        assert_eq!(game.boss.state, crate::boss::BossState::Waiting);
+
+        //有空位之后首领就出场了
+        game.monsters.list.clear();
+        let events = game.update(&Input::default(), STEP);
+        assert!(events.contains(&GameEvent::BossAppeared));
+        assert!(game.boss_health().is_some());
+    }
+
+    #[test]
+    fn game_ends_when_health_reaches_zero() {
+        let mut game = empty_game();
+        let damage = monster_on_player(&mut game);
+        game.player.health = damage;
+
+        let events = game.update(&Input::default(), STEP);
+        assert!(!events.iter().any(|e| matches!(e, GameEvent::GameOver(_))));
+        assert_eq!(game.player.health, 0);
+
+        let events = game.update(&Input::default(), STEP);
+        let Some(GameEvent::GameOver(stats)) = events.last() else {
+            panic!("生命值为 0 之后应该产生 GameOver 事件");
+        };
+        assert_eq!(stats.monster_attacks, 1);
+        assert_eq!(stats.game_time, game.game_time);
+    }
+}
@@ -1,4 +1,11 @@
 use macroquad::prelude::*;
+use crate::game::RunStats;
+use crate::date::Date;
+use crate::highscores::{HighScore, HighScores, HIGH_SCORES_PATH, MAX_NAME_LEN};
+use crate::menu::GameDifficulty;
+
+//分数明细每一行的动画时间（秒）：每隔这么久出现一行，数字在这段时间里从 0 涨到最终的分数
+const ROW_TIME: f32 = 0.5;
 
 //游戏结束的结构体
 pub struct GameOver {
@@ -10,27 +17,104 @@ pub struct GameOver {
     health_packs_collected: usize,
     //玩家被怪物攻击的次数
     monster_attacks: usize,
+    //玩家打死的怪物数量
+    kills: usize,
+    //是否打败了首领
+    boss_defeated: bool,
+    //到达的最高波次
+    best_wave: usize,
+    //这一局的随机种子，方便重玩同一局
+    seed: u64,
+    //这一局的统计信息和结束的日期，创下记录时写进排行榜
+    stats: RunStats,
+    date: Date,
+    //这一局的难度，为 None 时不记录到排行榜（例如播放录像）
+    difficulty: Option<GameDifficulty>,
+    scores: HighScores,
+    //创下记录时正在输入的名字，为 None 时不需要输入
+    name: Option<String>,
+    //保存之后所在的排行榜和名次（从 0 开始）
+    saved: Option<(GameDifficulty, usize)>,
+    //进入游戏结束界面之后经过的时间，用来播放分数明细的动画
+    elapsed: f32,
 }
 
 impl GameOver {
-    pub async fn new(game_time: f32, health_packs_collected: usize, monster_attacks: usize) -> Self {
+    //difficulty 为 None 时这一局不记录到排行榜
+    pub async fn new(stats: RunStats, difficulty: Option<GameDifficulty>) -> Self {
         //游戏结束界面的背景图片
         let background = load_texture("assets/game_over_background.png").await.unwrap();
+        //macroquad 不会自动清空字符队列，游戏中按过的键（例如攻击的 X）还留在里面，输入名字之前先清空
+        clear_input_queue();
+
+        //能上排行榜时让玩家输入名字（预先填好上一次的名字）
+        let date = Date::today();
+        let scores = HighScores::load_or_recover(HIGH_SCORES_PATH);
+        let name = difficulty
+            .filter(|&d| scores.rank(d, &HighScore::new("", &stats, date)).is_some())
+            .map(|_| scores.last_name.clone());
         
         //返回Gameover实例
         Self {
             background,
-            game_time,
-            health_packs_collected,
-            monster_attacks,
+            game_time: stats.game_time,
+            health_packs_collected: stats.health_packs_collected,
+            monster_attacks: stats.monster_attacks,
+            kills: stats.kills,
+            boss_defeated: stats.boss_defeated,
+            best_wave: stats.best_wave,
+            seed: stats.seed,
+            stats,
+            date,
+            difficulty,
+            scores,
+            name,
+            saved: None,
+            elapsed: 0.0,
         }
     }
 
-    pub fn update(&self) -> bool {
+    pub fn update(&mut self) -> bool {
+        self.elapsed += get_frame_time();
+
+        //输入名字：字母、数字、'-' 和 '_' 追加到名字后面，退格键删除一个字符，回车保存
+        if let Some(name) = &mut self.name {
+            while let Some(c) = get_char_pressed() {
+                if (c.is_ascii_alphanumeric() || c == '-' || c == '_') && name.len() < MAX_NAME_LEN {
+                    name.push(c);
+                }
+            }
+            if is_key_pressed(KeyCode::Backspace) {
+                name.pop();
+            }
+            if is_key_pressed(KeyCode::Enter) && !name.is_empty() {
+                self.save_score();
+            }
+            return false;
+        }
+
         // 检查玩家是否按下空格键，如果按空格键，则重新开始
         is_key_pressed(KeyCode::Space)
     }
 
+    //把这一局写进排行榜文件
+    fn save_score(&mut self) {
+        let (Some(name), Some(difficulty)) = (self.name.take(), self.difficulty) else {
+            return;
+        };
+        let rank = self.scores.insert(difficulty, HighScore::new(&name, &self.stats, self.date));
+        self.saved = rank.map(|rank| (difficulty, rank));
+        self.scores.last_name = name;
+        if let Err(e) = self.scores.save(HIGH_SCORES_PATH) {
+            eprintln!("{}", e);
+        }
+    }
+
+    //分数明细第 row 行的动画进度（0 到 1）
+    fn progress(&self, row: usize) -> f32 {
+        ((self.elapsed - row as f32 * ROW_TIME) / ROW_TIME).clamp(0.0, 1.0)
+    }
+
     //负责绘制游戏结束界面的背景、标题、统计信息和重新开始提示等内容的函数
     pub fn draw(&self) {
         // step1---绘制白色背景图
@@ -70,13 +154,22 @@ impl GameOver {
         let stats = format!(
             "Game Duration: {:.1} seconds\n\
              Health Packs Collected: {}\n\
-             Times Attacked: {}",
+             Times Attacked: {}\n\
+             Monsters Slain: {}\n\
+             Best Wave: {}\n\
+             Boss: {}\n\
+             Seed: {}",
             self.game_time, 
             self.health_packs_collected, 
-            self.monster_attacks
+            self.monster_attacks,
+            self.kills,
+            self.best_wave,
+            if self.boss_defeated { "Defeated!" } else { "Not defeated" },
+            self.seed
         );
         
-        let stats_size = 36.0;
+        //统计信息放在左边一栏，右边一栏是分数的明细
+        let stats_size = 26.0;
         let stats_lines = stats.split('\n').collect::<Vec<_>>();
         
         for (i, line) in stats_lines.iter().enumerate() {
@@ -84,20 +177,76 @@ impl GameOver {
             
             draw_text(
                 line,
-                screen_width() / 2.0 - line_width / 2.0 + 1.0,
-                screen_height() / 2.0 - 30.0 + (i as f32 * 50.0) + 1.0,
+                screen_width() / 4.0 - line_width / 2.0 + 1.0,
+                screen_height() / 2.0 - 100.0 + (i as f32 * 36.0) + 1.0,
                 stats_size,
                 BLACK,
             );
             draw_text(
                 line,
-                screen_width() / 2.0 - line_width / 2.0,
-                screen_height() / 2.0 - 30.0 + (i as f32 * 50.0),
+                screen_width() / 4.0 - line_width / 2.0,
+                screen_height() / 2.0 - 100.0 + (i as f32 * 36.0),
                 stats_size,
                 WHITE,
             );
         }
 
+        // step4.2---分数的明细：一行一行地出现，每一行的分数从 0 开始往上涨，最后乘以难度的倍率得到总分
+        let score = &self.stats.score;
+        let rows = [
+            (format!("Survival ({:.0}s)", self.game_time), score.survival),
+            (format!("Health Packs ({})", self.health_packs_collected), score.packs),
+            (format!("Damage Avoided ({})", score.avoided_hits), score.avoided),
+            (format!("Monsters Slain ({})", self.kills), score.kills),
+        ];
+        let top = screen_height() / 2.0 - 100.0;
+        for (i, (label, points)) in rows.iter().enumerate() {
+            let progress = self.progress(i);
+            if progress > 0.0 {
+                let value = format!("+{}", (*points as f32 * progress) as u32);
+                draw_score_row(label, &value, top + i as f32 * 36.0, 26.0, WHITE);
+            }
+        }
+        if self.progress(rows.len()) > 0.0 {
+            let label = format!("Difficulty ({:?})", self.stats.difficulty);
+            let value = format!("x{:.1}", score.multiplier);
+            draw_score_row(&label, &value, top + rows.len() as f32 * 36.0, 26.0, ORANGE);
+        }
+        let total_progress = self.progress(rows.len() + 1);
+        if total_progress > 0.0 {
+            let value = ((score.total as f32 * total_progress) as u32).to_string();
+            draw_score_row("SCORE", &value, top + (rows.len() + 1) as f32 * 36.0 + 16.0, 36.0, YELLOW);
+        }
+
+        // step4.5---创下记录时提示输入名字，保存之后显示名次
+        let record_text = match (&self.name, self.saved) {
+            (Some(name), _) => Some(format!("New high score! Name: {}_  (ENTER to save)", name)),
+            (None, Some((difficulty, rank))) => Some(format!("Saved as #{} on the {:?} board", rank + 1, difficulty)),
+            (None, None) => None,
+        };
+        if let Some(record_text) = record_text {
+            let record_size = 28.0;
+            let record_width = measure_text(&record_text, None, record_size as u16, 1.0).width;
+            draw_text(
+                &record_text,
+                screen_width() / 2.0 - record_width / 2.0 + 1.0,
+                screen_height() - 125.0 + 1.0,
+                record_size,
+                BLACK,
+            );
+            draw_text(
+                &record_text,
+                screen_width() / 2.0 - record_width / 2.0,
+                screen_height() - 125.0,
+                record_size,
+                ORANGE,
+            );
+        }
+        //输入名字的时候不显示重新开始的提示
+        if self.name.is_some() {
+            return;
+        }
+
         // step5---重新开始游戏的提示
         let restart_text = "Press SPACE to Restart";
         let restart_size = 30.0;
@@ -119,4 +268,15 @@ impl GameOver {
             GREEN,
         );
     }
-}
\ No newline at end of file
+}
+
+//在右边一栏绘制分数明细的一行：名字靠左，分数靠右
+fn draw_score_row(label: &str, value: &str, y: f32, size: f32, color: Color) {
+    let left = screen_width() / 2.0 + 20.0;
+    let right = screen_width() - 40.0;
+    let value_width = measure_text(value, None, size as u16, 1.0).width;
+    draw_text(label, left + 1.0, y + 1.0, size, BLACK);
+    draw_text(label, left, y, size, color);
+    draw_text(value, right - value_width + 1.0, y + 1.0, size, BLACK);
+    draw_text(value, right - value_width, y, size, color);
+}
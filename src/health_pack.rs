@@ -1,54 +1,85 @@
-use macroquad::prelude::*;
-use crate::{TILE_SIZE, Tile, MAP_WIDTH, MAP_HEIGHT};
+use ::rand::Rng;
+use crate::rng::GameRng;
+use crate::level::Level;
+use crate::map::Map;
+use serde::{Deserialize, Serialize};
 
 //单个血包的结构体
-//作用：用来表示游戏中的单个血包，包含血包的位置和样子。
+//作用：用来表示游戏中的单个血包，包含血包的位置。
+#[derive(Serialize, Deserialize)]
 pub struct HealthPack {
     pub x: usize,
     pub y: usize,
-    pub texture: Texture2D,
 }
 
 //血包集合的结构体
-//作用：管理游戏中的所有血包，包含血包列表、计时器和血包样子等信息。
+//作用：管理游戏中的所有血包，包含血包列表、计时器和可以刷新血包的格子等信息。
+#[derive(Serialize, Deserialize)]
 pub struct HealthPacks {
     pub list: Vec<HealthPack>,
     pub timer: f32,
-    pub texture: Texture2D,
+    //刷新血包的间隔（秒）
+    spawn_interval: f32,
+    //可以刷新血包的地板格子（来自关卡的血包区域）
+    spawn_tiles: Vec<(usize, usize)>,
 }
 
 impl HealthPacks {
-    //创建血包集合的函数
-    pub async fn new() -> Self {
-        let texture = load_texture("assets/health_pack.png").await.unwrap();
-        texture.set_filter(FilterMode::Nearest);
+    //创建血包集合的函数，spawn_interval 为刷新血包的间隔（秒）
+    pub fn new(level: &Level, spawn_interval: f32) -> Self {
+        //关卡没有指定血包区域时，所有地板都可以刷新血包
+        let spawn_tiles = if level.health_pack_zones.is_empty() {
+            level.map.floor_tiles()
+        } else {
+            let mut tiles: Vec<(usize, usize)> = level
+                .health_pack_zones
+                .iter()
+                .flat_map(|zone| zone.tiles())
+                .filter(|&(x, y)| level.map.is_floor(x, y))
+                .collect();
+            //多个区域重叠时去掉重复的格子
+            tiles.sort();
+            tiles.dedup();
+            tiles
+        };
 
         //返回血包集合的实例
         Self {
             list: vec![],
             timer: 0.0,
-            texture,
+            spawn_interval,
+            spawn_tiles,
         }
     }
 
- pub fn update(&mut self, delta: f32, _map: &[[Tile; MAP_WIDTH]; MAP_HEIGHT]) {
+    //血包和可以刷新血包的格子是否都在这张地图的地板上（至少要有一个可以刷新的格子）
+    pub fn fits(&self, map: &Map) -> bool {
+        !self.spawn_tiles.is_empty()
+            && self.spawn_tiles.iter().all(|&(x, y)| map.is_floor(x, y))
+            && self.list.iter().all(|pack| map.is_floor(pack.x, pack.y))
+    }
+
+    //修改刷新血包的间隔（动态难度会调整它）
+    pub fn set_spawn_interval(&mut self, spawn_interval: f32) {
+        self.spawn_interval = spawn_interval;
+    }
+
+ pub fn update(&mut self, delta: f32, rng: &mut GameRng) {
     //更新血包生成的计时器
     self.timer += delta;
-    //如果计时器达到5秒，生成血包or更新当前血包的位置
-    if self.timer >= 5.0 {
+    //如果计时器达到刷新间隔，生成血包or更新当前血包的位置
+    if self.timer >= self.spawn_interval {
         //重置血包计时器
         self.timer = 0.0;
 
-        //随机生成血包的坐标
-        let new_x = rand::gen_range(1, MAP_WIDTH - 1);
-        let new_y = rand::gen_range(1, MAP_HEIGHT - 1);
+        //在可以刷新血包的格子中随机选择一个
+        let (new_x, new_y) = self.spawn_tiles[rng.gen_range(0..self.spawn_tiles.len())];
 
         //如果血包列表空了，则创建一个新的血包
         if self.list.is_empty() {
             self.list.push(HealthPack {
                 x: new_x,
                 y: new_y,
-                texture: self.texture.clone(),
             });
         } else {
             // 刷新已有血包位置
@@ -57,16 +88,4 @@ impl HealthPacks {
         }
     }
 }
-
-    //绘制血包的函数，负责将血包显示在屏幕
-    pub fn draw(&self) {
-        for pack in &self.list {
-            draw_texture(
-                &pack.texture,
-                pack.x as f32 * TILE_SIZE,
-                pack.y as f32 * TILE_SIZE,
-                WHITE,
-            );
-        }
-    }
 }
@@ -0,0 +1,83 @@
+use macroquad::prelude::*;
+use crate::highscores::{HighScores, HIGH_SCORES_PATH};
+use crate::menu::{GameDifficulty, ALL_DIFFICULTIES};
+
+//排行榜界面：左右键切换难度，一次显示一个难度的排行榜
+pub struct HighScoreScreen {
+    scores: HighScores,
+    //正在显示的难度
+    difficulty: GameDifficulty,
+}
+
+impl HighScoreScreen {
+    //每次打开时重新读取排行榜文件，difficulty 为先显示的难度
+    pub fn new(difficulty: GameDifficulty) -> Self {
+        Self {
+            scores: HighScores::load_or_recover(HIGH_SCORES_PATH),
+            difficulty,
+        }
+    }
+
+    //返回 true 表示回到菜单
+    pub fn update(&mut self) -> bool {
+        let index = ALL_DIFFICULTIES.iter().position(|&d| d == self.difficulty).unwrap_or(0);
+        if is_key_pressed(KeyCode::Left) {
+            self.difficulty = ALL_DIFFICULTIES[(index + ALL_DIFFICULTIES.len() - 1) % ALL_DIFFICULTIES.len()];
+        }
+        if is_key_pressed(KeyCode::Right) {
+            self.difficulty = ALL_DIFFICULTIES[(index + 1) % ALL_DIFFICULTIES.len()];
+        }
+        is_key_pressed(KeyCode::Escape) || is_key_pressed(KeyCode::H)
+    }
+
+    //绘制排行榜（背景由菜单负责绘制）
+    pub fn draw(&self) {
+        draw_rectangle(0.0, 0.0, screen_width(), screen_height(), Color::new(0.0, 0.0, 0.0, 0.7));
+
+        let title = format!("High Scores - {:?}", self.difficulty);
+        let title_size = 48.0;
+        let title_width = measure_text(&title, None, title_size as u16, 1.0).width;
+        draw_text(&title, screen_width() / 2.0 - title_width / 2.0 + 2.0, 90.0 + 2.0, title_size, BLACK);
+        draw_text(&title, screen_width() / 2.0 - title_width / 2.0, 90.0, title_size, YELLOW);
+
+        //表格的每一列的横坐标
+        let columns = [20.0, 50.0, 170.0, 250.0, 330.0, 395.0, 445.0, 565.0];
+        let headers = ["#", "Name", "Score", "Time", "Packs", "Hits", "Date", "Seed"];
+        let row_size = 20.0;
+        for (header, x) in headers.iter().zip(columns) {
+            draw_text(header, x, 150.0, row_size, ORANGE);
+        }
+
+        let table = self.scores.table(self.difficulty);
+        if table.is_empty() {
+            draw_text("No records yet", columns[0], 190.0, row_size, WHITE);
+        }
+        for (i, entry) in table.iter().enumerate() {
+            let cells = [
+                format!("{}", i + 1),
+                entry.name.clone(),
+                entry.score.to_string(),
+                format!("{:.1}s", entry.game_time),
+                entry.health_packs_collected.to_string(),
+                entry.monster_attacks.to_string(),
+                entry.date.to_string(),
+                entry.seed.to_string(),
+            ];
+            let y = 190.0 + i as f32 * 34.0;
+            for (cell, x) in cells.iter().zip(columns) {
+                draw_text(cell, x, y, row_size, WHITE);
+            }
+        }
+
+        let hint = "LEFT/RIGHT: change difficulty    ESC or H: back";
+        let hint_size = 20.0;
+        let hint_width = measure_text(hint, None, hint_size as u16, 1.0).width;
+        draw_text(
+            hint,
+            screen_width() / 2.0 - hint_width / 2.0,
+            screen_height() - 30.0,
+            hint_size,
+            Color::new(0.4, 0.8, 1.0, 1.0),
+        );
+    }
+}
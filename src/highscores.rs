@@ -0,0 +1,148 @@
+//排行榜模块：每个难度保存得分最高的前 MAX_ENTRIES 局，存放在 data/high_scores.json 中
+//读取时重新排序并截断每个难度的记录（手动改过的文件可能不符合要求），文件的读写和损坏文件的处理交给 storage 模块
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::date::Date;
+use crate::storage::{load_json_or_recover, save_json};
+use crate::game::RunStats;
+use crate::menu::GameDifficulty;
+
+//排行榜文件
+pub const HIGH_SCORES_PATH: &str = "data/high_scores.json";
+//每个难度保存的记录数量
+pub const MAX_ENTRIES: usize = 10;
+//玩家名字的最大长度
+pub const MAX_NAME_LEN: usize = 12;
+
+//排行榜上的一条记录
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HighScore {
+    pub name: String,
+    //这一局的总分（旧版本的记录没有分数，当作 0 分）
+    #[serde(default)]
+    pub score: u32,
+    //存活的时间（秒）
+    pub game_time: f32,
+    pub health_packs_collected: usize,
+    pub monster_attacks: usize,
+    //这一局的随机种子，可以用它重玩同一局
+    pub seed: u64,
+    //这一局结束的日期
+    pub date: Date,
+}
+
+impl HighScore {
+    pub fn new(name: &str, stats: &RunStats, date: Date) -> Self {
+        Self {
+            name: name.to_string(),
+            score: stats.score.total,
+            game_time: stats.game_time,
+            health_packs_collected: stats.health_packs_collected,
+            monster_attacks: stats.monster_attacks,
+            seed: stats.seed,
+            date,
+        }
+    }
+
+    //这条记录是否比 other 好：分数更高，分数一样时存活时间更长
+    fn beats(&self, other: &HighScore) -> bool {
+        self.score > other.score || (self.score == other.score && self.game_time > other.game_time)
+    }
+}
+
+//所有难度的排行榜
+#[derive(Default, Serialize, Deserialize)]
+pub struct HighScores {
+    //上一次输入的名字，下次创下记录时预先填好
+    #[serde(default)]
+    pub last_name: String,
+    #[serde(default)]
+    tables: HashMap<GameDifficulty, Vec<HighScore>>,
+}
+
+impl HighScores {
+    //读取排行榜，文件不存在或者损坏时返回空的排行榜（损坏的文件改名留着）
+    pub fn load_or_recover(path: &str) -> Self {
+        let mut scores: Self = load_json_or_recover(path, "排行榜文件");
+        //手动改过的文件可能没有排好序或者超过了数量
+        for table in scores.tables.values_mut() {
+            table.sort_by(|a, b| b.score.cmp(&a.score).then(b.game_time.total_cmp(&a.game_time)));
+            table.truncate(MAX_ENTRIES);
+        }
+        scores
+    }
+
+    //把排行榜保存到文件
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        save_json(path, self, "排行榜文件")
+    }
+
+    //某个难度的排行榜（从好到差排列）
+    pub fn table(&self, difficulty: GameDifficulty) -> &[HighScore] {
+        self.tables.get(&difficulty).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    //这一局能排在第几名（从 0 开始），上不了榜时返回 None
+    pub fn rank(&self, difficulty: GameDifficulty, entry: &HighScore) -> Option<usize> {
+        let table = self.table(difficulty);
+        let rank = table.iter().take_while(|other| !entry.beats(other)).count();
+        (rank < MAX_ENTRIES).then_some(rank)
+    }
+
+    //把一局加入排行榜，返回它的名次（从 0 开始），上不了榜时返回 None
+    pub fn insert(&mut self, difficulty: GameDifficulty, entry: HighScore) -> Option<usize> {
+        let rank = self.rank(difficulty, &entry)?;
+        let table = self.tables.entry(difficulty).or_default();
+        table.insert(rank, entry);
+        table.truncate(MAX_ENTRIES);
+        Some(rank)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn entry(score: u32, game_time: f32) -> HighScore {
+        HighScore {
+            name: "test".to_string(),
+            score,
+            game_time,
+            health_packs_collected: 0,
+            monster_attacks: 0,
+            seed: 0,
+            date: Date::from_days(0),
+        }
+    }
+
+    #[test]
+    fn keeps_the_best_runs_per_difficulty() {
+        let mut scores = HighScores::default();
+        for i in 0..MAX_ENTRIES {
+            scores.insert(GameDifficulty::Easy, entry(100 + i as u32, 60.0));
+        }
+        //比榜上最差的还差，上不了榜；其他难度不受影响
+        assert_eq!(scores.insert(GameDifficulty::Easy, entry(50, 60.0)), None);
+        assert_eq!(scores.insert(GameDifficulty::Hard, entry(50, 60.0)), Some(0));
+
+        //分数一样时存活时间长的排在前面，最差的一条被挤掉
+        assert_eq!(scores.insert(GameDifficulty::Easy, entry(109, 30.0)), Some(1));
+        assert_eq!(scores.insert(GameDifficulty::Easy, entry(109, 45.0)), Some(1));
+        let table = scores.table(GameDifficulty::Easy);
+        assert_eq!(table.len(), MAX_ENTRIES);
+        assert_eq!(table.last().unwrap().score, 102);
+    }
+
+    #[test]
+    fn corrupted_file_is_set_aside() {
+        let path = std::env::temp_dir().join(format!("high_scores_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        fs::write(path, "{ not json").unwrap();
+
+        let scores = HighScores::load_or_recover(path);
+        assert!(scores.table(GameDifficulty::Easy).is_empty());
+        assert!(fs::metadata(path).is_err());
+        fs::remove_file(format!("{}.corrupt", path)).unwrap();
+    }
+}
@@ -1,14 +1,15 @@
 use macroquad::prelude::*;
+use crate::TILE_SIZE;
 
 //绘制玩家生命值条的函数
-pub fn draw_health_bar(health: i32) {
+pub fn draw_health_bar(health: i32, max_health: i32) {
     let bar_width = 300.0;
     let bar_height = 20.0;
     //居中显示生命值条
     let bar_x = screen_width() / 2.0 - bar_width / 2.0;
     let bar_y = 10.0;
 
-    let health_ratio = health.clamp(0, 100) as f32 / 100.0;
+    let health_ratio = health.clamp(0, max_health) as f32 / max_health as f32;
 
     draw_rectangle(bar_x, bar_y, bar_width, bar_height, DARKGRAY);
     draw_rectangle(bar_x, bar_y, bar_width * health_ratio, bar_height, GREEN);
@@ -20,6 +21,55 @@ pub fn draw_health_bar(health: i32) {
     draw_text(&text, bar_x + bar_width / 2.0 - text_dim.width / 2.0, bar_y + bar_height - 4.0, font_size, WHITE);
 }
 
+//绘制首领的生命值条（画在玩家血条的下面）
+pub fn draw_boss_health_bar(health: i32, max_health: i32) {
+    let bar_width = 300.0;
+    let bar_height = 14.0;
+    let bar_x = screen_width() / 2.0 - bar_width / 2.0;
+    let bar_y = 36.0;
+
+    let health_ratio = health.clamp(0, max_health) as f32 / max_health as f32;
+
+    draw_rectangle(bar_x, bar_y, bar_width, bar_height, DARKGRAY);
+    draw_rectangle(bar_x, bar_y, bar_width * health_ratio, bar_height, RED);
+
+    let text = format!("BOSS: {}", health.max(0));
+    let font_size = 16.0;
+    let text_dim = measure_text(&text, None, font_size as u16, 1.0);
+    draw_text(&text, bar_x + bar_width / 2.0 - text_dim.width / 2.0, bar_y + bar_height - 3.0, font_size, WHITE);
+}
+
+//在左上角绘制当前的波次
+pub fn draw_wave(wave: usize) {
+    let text = format!("Wave {}", wave);
+    draw_text(&text, 12.0 + 1.0, 30.0 + 1.0, 28.0, BLACK);
+    draw_text(&text, 12.0, 30.0, 28.0, ORANGE);
+}
+
+//在波次下面绘制动态难度当前的压力
+pub fn draw_pressure(pressure: f32) {
+    let text = format!("Intensity x{:.1}", pressure);
+    draw_text(&text, 12.0, 54.0, 20.0, LIGHTGRAY);
+}
+
+//成就解锁的提示从右上角滑进来，显示 TOAST_TIME 秒之后消失
+pub const TOAST_TIME: f32 = 3.0;
+
+//绘制成就解锁的提示，age 为已经显示的时间（秒）
+pub fn draw_achievement_toast(title: &str, description: &str, age: f32) {
+    let width = 300.0;
+    let height = 56.0;
+    //前 0.3 秒滑进来，最后 0.3 秒滑出去
+    let slide = (age / 0.3).min((TOAST_TIME - age) / 0.3).clamp(0.0, 1.0);
+    let x = screen_width() - (width + 10.0) * slide;
+    let y = 60.0;
+
+    draw_rectangle(x, y, width, height, Color::new(0.1, 0.1, 0.1, 0.9));
+    draw_rectangle_lines(x, y, width, height, 2.0, GOLD);
+    draw_text(&format!("Achievement: {}", title), x + 10.0, y + 22.0, 22.0, GOLD);
+    draw_text(description, x + 10.0, y + 44.0, 18.0, WHITE);
+}
+
 //绘制消息文本的函数
 pub fn draw_message(message: &str, color: Color) {
     let font_size = 40.0;  // 从30增大到40
@@ -31,3 +81,66 @@ pub fn draw_message(message: &str, color: Color) {
     draw_text(message, x, y, font_size, color);
     draw_text(message, x+1.0, y+1.0, font_size, color); 
 }
+
+// 绘制按钮的函数，按钮被点击时返回 true
+pub fn draw_button(pos: Vec2, size: Vec2, label: &str, font_size: Option<u16>) -> bool {
+    let button_color = Color::new(0.2, 0.2, 0.2, 1.0);
+    let hover_color = Color::new(0.3, 0.3, 0.3, 1.0);
+    let text_color = WHITE;
+
+    let mouse_pos = mouse_position();
+    let button_rect = Rect::new(pos.x, pos.y, size.x, size.y);
+    let is_hovered = button_rect.contains(Vec2::new(mouse_pos.0, mouse_pos.1));
+
+    draw_rectangle(pos.x, pos.y, size.x, size.y, if is_hovered { hover_color } else { button_color });
+    
+    let text_size = measure_text(label, None, font_size.unwrap_or(20), 1.0);
+    draw_text_ex(
+        label,
+        pos.x + size.x / 2.0 - text_size.width / 2.0,
+        pos.y + size.y / 2.0 + text_size.height / 2.0,
+        TextParams {
+            font_size: font_size.unwrap_or(20),
+            color: text_color,
+            ..Default::default()
+        },
+    );
+
+    is_hovered && is_mouse_button_pressed(MouseButton::Left)
+}
+
+//游戏主页面暂停按钮的渲染，按钮被点击时返回 true
+pub fn draw_pause_button(paused: bool) -> bool {
+    let label = if paused { "Restart" } else { "Pause" };
+
+    let button_width = 160.0;
+    let button_height = 60.0;
+
+    //按钮固定在屏幕的右上角
+    let button_pos = Vec2::new(
+        screen_width() - TILE_SIZE * 2.55,
+        TILE_SIZE * 0.04,
+    );
+
+    draw_button(
+        button_pos,
+        Vec2::new(button_width, button_height),
+        label,
+        Some(28),
+    )
+}
+
+//暂停时在暂停按钮的下面绘制 "Save & Quit" 按钮，按钮被点击时返回 true
+pub fn draw_save_quit_button() -> bool {
+    let button_pos = Vec2::new(
+        screen_width() - TILE_SIZE * 2.55,
+        TILE_SIZE * 0.04 + 70.0,
+    );
+
+    draw_button(
+        button_pos,
+        Vec2::new(160.0, 50.0),
+        "Save & Quit",
+        Some(24),
+    )
+}
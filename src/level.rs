@@ -0,0 +1,223 @@
+//关卡模块：从 JSON 文件中读取地图、玩家出生点、怪物出生点和血包刷新区域
+//地图的大小由 tiles 的行数和每行的长度决定（每一行必须一样长）
+//文件格式示例（'#' 为墙壁，'.' 为地板，坐标均为格子坐标 [x, y]）：
+//{
+//  "name": "Arena",
+//  "tiles": ["############", "#..........#", ...],
+//  "player_start": [3, 3],
+//  "monster_spawns": [[8, 6], [9, 2]],
+//  "health_pack_zones": [{ "x": 1, "y": 1, "width": 10, "height": 8 }]
+//}
+use std::fs;
+use serde::{Deserialize, Serialize};
+use crate::map::{Map, Tile};
+
+//默认关卡的大小
+const ARENA_WIDTH: usize = 12;
+const ARENA_HEIGHT: usize = 10;
+
+//血包刷新区域（一个矩形范围）
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct Zone {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Zone {
+    //区域内所有格子的坐标
+    pub fn tiles(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (self.y..self.y + self.height).flat_map(move |y| (self.x..self.x + self.width).map(move |x| (x, y)))
+    }
+}
+
+//关卡文件中的原始数据，读取之后需要经过校验才能变成 Level
+#[derive(Serialize, Deserialize)]
+struct LevelFile {
+    #[serde(default)]
+    name: String,
+    tiles: Vec<String>,
+    player_start: (usize, usize),
+    #[serde(default)]
+    monster_spawns: Vec<(usize, usize)>,
+    #[serde(default)]
+    health_pack_zones: Vec<Zone>,
+}
+
+//经过校验的关卡
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "LevelFile", into = "LevelFile")]
+pub struct Level {
+    pub name: String,
+    pub map: Map,
+    pub player_start: (usize, usize),
+    //怪物出生点，为空时怪物在任意地板上出生
+    pub monster_spawns: Vec<(usize, usize)>,
+    //血包刷新区域，为空时血包在任意地板上刷新
+    pub health_pack_zones: Vec<Zone>,
+}
+
+impl Level {
+    //默认关卡：四周是墙壁的空旷场地，玩家从 (3, 3) 出发
+    pub fn default_arena() -> Self {
+        Self {
+            name: "Arena".to_string(),
+            map: Map::walled(ARENA_WIDTH, ARENA_HEIGHT),
+            player_start: (3, 3),
+            monster_spawns: Vec::new(),
+            health_pack_zones: Vec::new(),
+        }
+    }
+
+    //从文件读取关卡
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("无法读取关卡文件 {}: {}", path, e))?;
+        Self::parse(&text).map_err(|e| format!("关卡文件 {} 有误: {}", path, e))
+    }
+
+    //从 JSON 文本解析关卡
+    pub fn parse(text: &str) -> Result<Self, String> {
+        serde_json::from_str(text).map_err(|e| e.to_string())
+    }
+
+}
+
+impl TryFrom<LevelFile> for Level {
+    type Error = String;
+
+    //校验关卡文件，出错时返回说明具体位置的错误信息
+    fn try_from(file: LevelFile) -> Result<Self, String> {
+        let height = file.tiles.len();
+        let width = file.tiles.first().map_or(0, |line| line.chars().count());
+        if width < 3 || height < 3 {
+            return Err(format!("地图至少需要 3x3 个格子，实际为 {}x{}", width, height));
+        }
+
+        let mut map = Map::new(width, height, Tile::Floor);
+        for (y, line) in file.tiles.iter().enumerate() {
+            let chars: Vec<char> = line.chars().collect();
+            if chars.len() != width {
+                return Err(format!("第 {} 行应有 {} 个格子（与第 0 行一样长），实际有 {} 个", y, width, chars.len()));
+            }
+            for (x, c) in chars.into_iter().enumerate() {
+                let tile = match c {
+                    '#' => Tile::Wall,
+                    '.' => Tile::Floor,
+                    other => return Err(format!("格子 ({}, {}) 处有未知字符 '{}'，只能使用 '#' 或 '.'", x, y, other)),
+                };
+                map.set(x, y, tile);
+            }
+        }
+
+        let level = Level {
+            name: file.name,
+            map,
+            player_start: file.player_start,
+            monster_spawns: file.monster_spawns,
+            health_pack_zones: file.health_pack_zones,
+        };
+
+        let (px, py) = level.player_start;
+        if !level.map.is_floor(px, py) {
+            return Err(format!("玩家出生点 ({}, {}) 不在地板上", px, py));
+        }
+
+        for &(mx, my) in &level.monster_spawns {
+            if !level.map.is_floor(mx, my) {
+                return Err(format!("怪物出生点 ({}, {}) 不在地板上", mx, my));
+            }
+            if (mx, my) == level.player_start {
+                return Err(format!("怪物出生点 ({}, {}) 与玩家出生点重合", mx, my));
+            }
+        }
+
+        for zone in &level.health_pack_zones {
+            //用 checked_add：很大的坐标相加会溢出
+            let fits = |start: usize, length: usize, limit: usize| start.checked_add(length).is_some_and(|end| end <= limit);
+            if zone.width == 0
+                || zone.height == 0
+                || !fits(zone.x, zone.width, level.map.width())
+                || !fits(zone.y, zone.height, level.map.height())
+            {
+                return Err(format!(
+                    "血包区域 (x: {}, y: {}, width: {}, height: {}) 超出了地图范围",
+                    zone.x, zone.y, zone.width, zone.height
+                ));
+            }
+            if !zone.tiles().any(|(x, y)| level.map.is_floor(x, y)) {
+                return Err(format!("血包区域 (x: {}, y: {}) 内没有任何地板", zone.x, zone.y));
+            }
+        }
+
+        Ok(level)
+    }
+}
+
+impl From<Level> for LevelFile {
+    fn from(level: Level) -> Self {
+        let tiles = (0..level.map.height())
+            .map(|y| {
+                (0..level.map.width())
+                    .map(|x| if level.map.is_floor(x, y) { '.' } else { '#' })
+                    .collect()
+            })
+            .collect();
+
+        Self {
+            name: level.name,
+            tiles,
+            player_start: level.player_start,
+            monster_spawns: level.monster_spawns,
+            health_pack_zones: level.health_pack_zones,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    //一个 5x4 的小关卡，extra 中的字段覆盖默认值
+    fn parse(extra: serde_json::Value) -> Result<Level, String> {
+        let mut file = json!({
+            "tiles": ["#####", "#...#", "#.#.#", "#####"],
+            "player_start": [1, 1],
+        });
+        for (key, value) in extra.as_object().unwrap() {
+            file[key] = value.clone();
+        }
+        Level::parse(&file.to_string())
+    }
+
+    #[test]
+    fn valid_level_is_accepted() {
+        let level = parse(json!({ "monster_spawns": [[3, 2]], "health_pack_zones": [{ "x": 1, "y": 1, "width": 3, "height": 1 }] })).unwrap();
+        assert_eq!((level.map.width(), level.map.height()), (5, 4));
+        assert!(!level.map.is_floor(2, 2));
+    }
+
+    #[test]
+    fn invalid_levels_report_the_problem() {
+        let error = |extra| parse(extra).err().unwrap();
+        assert!(error(json!({ "tiles": ["###", "#.#"] })).contains("3x3"));
+        assert!(error(json!({ "tiles": ["#####", "#...#", "#..#", "#####"] })).contains("第 2 行"));
+        assert!(error(json!({ "tiles": ["#####", "#.x.#", "#...#", "#####"] })).contains("'x'"));
+        assert!(error(json!({ "player_start": [2, 2] })).contains("玩家出生点"));
+        assert!(error(json!({ "monster_spawns": [[0, 0]] })).contains("怪物出生点 (0, 0) 不在地板上"));
+        assert!(error(json!({ "monster_spawns": [[1, 1]] })).contains("与玩家出生点重合"));
+        assert!(error(json!({ "health_pack_zones": [{ "x": 3, "y": 1, "width": 3, "height": 1 }] })).contains("超出了地图范围"));
+        assert!(error(json!({ "health_pack_zones": [{ "x": 1, "y": 1, "width": usize::MAX, "height": 1 }] })).contains("超出了地图范围"));
+        assert!(error(json!({ "health_pack_zones": [{ "x": 0, "y": 0, "width": 5, "height": 1 }] })).contains("没有任何地板"));
+    }
+
+    #[test]
+    fn shipped_levels_load() {
+        for entry in fs::read_dir("levels").unwrap() {
+            let path = entry.unwrap().path();
+            let level = Level::load(path.to_str().unwrap()).unwrap();
+            assert!(!level.name.is_empty(), "{:?} 没有名字", path);
+        }
+    }
+}
@@ -1,339 +1,216 @@
+mod game;
 mod hud;
 mod monster;
 mod health_pack;
 mod menu;
 mod game_over;
-
-//引入macroquad 库的预导入模块和数学模块
+mod render;
+mod replay;
+mod level;
+mod map;
+mod camera;
+mod dungeon;
+mod pathfinding;
+mod ai;
+mod monster_kind;
+mod projectile;
+mod boss;
+mod waves;
+mod dynamic;
+mod custom;
+mod custom_editor;
+mod date;
+mod highscores;
+mod high_score_screen;
+mod achievements;
+mod achievement_screen;
+mod daily;
+mod definitions;
+mod scoring;
+mod fov;
+mod timestep;
+mod rng;
+mod save;
+mod storage;
+
+//引入macroquad 库的预导入模块
 use macroquad::prelude::*;
-use macroquad::math::Vec2; 
 //引入main函数中需要使用的所有其他本项目自定义的模块中含有的结构体or函数
-use hud::{draw_health_bar, draw_message};
-use monster::Monsters;
-use health_pack::HealthPacks;
-use menu::{Menu, GameDifficulty};
+use hud::{draw_achievement_toast, draw_boss_health_bar, draw_health_bar, draw_message, draw_pause_button, draw_pressure, draw_save_quit_button, draw_wave, TOAST_TIME};
+use game::{Game, GameEvent, Input};
+use menu::{Menu, MenuChoice, GameDifficulty};
 use game_over::GameOver;
+use render::{camera_target, Renderer};
+use camera::Camera;
+use dungeon::{DungeonStyle, DUNGEON_WIDTH, DUNGEON_HEIGHT};
+use replay::{Replay, ReplayPlayer};
+use level::Level;
+use definitions::Definitions;
+use timestep::{FixedTimestep, DEFAULT_TICK_RATE};
+use achievements::{Achievement, Achievements, ACHIEVEMENTS_PATH};
+use date::Date;
+use daily::{DailyChallenge, DailyHistory, DAILY_HISTORY_PATH};
+use save::SAVE_PATH;
 
 //地图格子的大小
 const TILE_SIZE: f32 = 64.0;
-//游戏地图大小
-const MAP_WIDTH: usize = 12;
-const MAP_HEIGHT: usize = 10;
-
-//地图的格子有两种类型：一种是墙壁、一种是地板
-#[derive(Clone, Copy, PartialEq)]
-pub enum Tile {
-    Wall,
-    Floor,
+//窗口大小的范围：地图较小时使用最小尺寸，地图较大时窗口最多放大到最大尺寸
+const MIN_WINDOW_SIZE: (f32, f32) = (768.0, 640.0);
+const MAX_WINDOW_SIZE: (f32, f32) = (1280.0, 832.0);
+
+//读取形如 `--name value` 的命令行参数
+fn arg_value(name: &str) -> Option<String> {
+    let args: Vec<String> = std::env::args().collect();
+    let pos = args.iter().position(|a| a == name)?;
+    args.get(pos + 1).cloned()
 }
 
-//玩家结构体
-pub struct Player {
-    pub x: usize,
-    pub y: usize,
-    pub health: i32,
-    pub texture: Texture2D,
-    pub health_packs_collected: usize,
-    pub monster_attacks: usize,
+//从命令行参数中读取随机种子，用法：rust_final --seed 12345
+fn seed_from_args() -> Option<u64> {
+    arg_value("--seed")?.parse().ok()
 }
 
-//玩家结构体的方法
-impl Player {
-    //玩家受到怪物攻击的方法
-    pub fn take_damage(&mut self, amount: i32) {
-        println!("玩家受到伤害: {} HP", amount);
-        self.health -= amount;
-        self.monster_attacks += 1;
-        if self.health < 0 {
-            self.health = 0;
+//从命令行参数中读取关卡文件，用法：rust_final --level levels/corridors.json
+//没有指定或文件有误时使用默认的空旷场地
+fn level_from_args() -> Level {
+    match arg_value("--level").map(|path| Level::load(&path)) {
+        Some(Ok(level)) => level,
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            Level::default_arena()
         }
+        None => Level::default_arena(),
     }
+}
 
-    //玩家吃血包回血的方法
-    pub fn heal(&mut self, amount: i32) {
-        self.health += amount;
-        self.health_packs_collected += 1;
-        if self.health > 100 {
-            self.health = 100;
+//读取数值定义文件，用法：rust_final --defs my_balance.toml（默认读取 data/definitions.toml）
+//文件有误时打印错误信息，并使用内置的默认数值
+fn definitions_from_args() -> Definitions {
+    let path = arg_value("--defs").unwrap_or_else(|| definitions::DEFAULT_PATH.to_string());
+    match Definitions::load(&path) {
+        Ok(definitions) => definitions,
+        Err(e) => {
+            eprintln!("{}", e);
+            Definitions::builtin()
         }
     }
 }
 
-// 绘制按钮的函数
-fn draw_button(pos: Vec2, size: Vec2, label: &str, font_size: Option<u16>) -> bool {
-    let button_color = Color::new(0.2, 0.2, 0.2, 1.0);
-    let hover_color = Color::new(0.3, 0.3, 0.3, 1.0);
-    let text_color = WHITE;
-
-    let mouse_pos = mouse_position();
-    let button_rect = Rect::new(pos.x, pos.y, size.x, size.y);
-    let is_hovered = button_rect.contains(Vec2::new(mouse_pos.0, mouse_pos.1));
-
-    draw_rectangle(pos.x, pos.y, size.x, size.y, if is_hovered { hover_color } else { button_color });
-    
-    let text_size = measure_text(label, None, font_size.unwrap_or(20), 1.0);
-    draw_text_ex(
-        label,
-        pos.x + size.x / 2.0 - text_size.width / 2.0,
-        pos.y + size.y / 2.0 + text_size.height / 2.0,
-        TextParams {
-            font_size: font_size.unwrap_or(20),
-            color: text_color,
-            ..Default::default()
-        },
-    );
-
-    is_hovered && is_mouse_button_pressed(MouseButton::Left)
-}
-
-//定义游戏结构体
-pub struct Game {
-    map: [[Tile; MAP_WIDTH]; MAP_HEIGHT], // 地图二维数组
-    wall_texture: Texture2D, // 墙壁的纹理
-    floor_texture: Texture2D, // 地板的纹理
-    player: Player, // 玩家对象
-    monsters: Monsters, // 怪物集合
-    health_packs: HealthPacks, // 血包集合
-    pub message: Option<(String, Color)>, // 游戏中的提示消息
-    message_timer: f32, // 消息显示的计时器
-    damage_cooldown: f32, // 玩家受到伤害后的冷却时间
-    game_time: f32, // 游戏进行的时间
-    pub paused: bool, // 游戏是否暂停
-
+//从命令行参数中读取每秒的模拟次数，用法：rust_final --tick-rate 120
+fn tick_rate_from_args() -> f32 {
+    arg_value("--tick-rate")
+        .and_then(|rate| rate.parse().ok())
+        .filter(|rate: &f32| rate.is_finite() && *rate > 0.0)
+        .unwrap_or(DEFAULT_TICK_RATE)
 }
 
-//游戏结构体的相关方法
-impl Game {
-    //异步创建游戏的实例的方法
-    pub async fn new(difficulty: GameDifficulty) -> Self {
-        //墙壁的图片加载
-        let wall_texture = load_texture("assets/wall.png").await.unwrap();
-        wall_texture.set_filter(FilterMode::Nearest);
-
-        //地板的图片加载
-        let floor_texture = load_texture("assets/floor.png").await.unwrap();
-        floor_texture.set_filter(FilterMode::Nearest);
-
-        //玩家的图片加载
-        let player_texture = load_texture("assets/player.png").await.unwrap();
-        player_texture.set_filter(FilterMode::Nearest);
-
-        //一开始整张地图都是地板
-        let mut map = [[Tile::Floor; MAP_WIDTH]; MAP_HEIGHT];
-        //然后再将游戏界面的边缘变成墙壁
-        for y in 0..MAP_HEIGHT {
-            for x in 0..MAP_WIDTH {
-                if x == 0 || x == MAP_WIDTH - 1 || y == 0 || y == MAP_HEIGHT - 1 {
-                    map[y][x] = Tile::Wall;
-                }
-            }
-        }
-
-        //初始化玩家对象
-        let player = Player {
-            x: 3,
-            y: 3,
-            health: 100,
-            texture: player_texture,
-            health_packs_collected: 0,
-            monster_attacks: 0,
-        };
-
-        //根据游戏难度设置怪物的数量
-        let monster_count = match difficulty {
-            GameDifficulty::Easy => 1,
-            GameDifficulty::Medium => 3,
-            GameDifficulty::Hard => 5,
-        };
-
-        //怪物集合
-        let monsters = Monsters::new(monster_count).await;
-        //血包集合
-        let health_packs = HealthPacks::new().await;
-
-        //返回游戏的实例
-        Self {
-            map,
-            wall_texture,
-            floor_texture,
-            player,
-            monsters,
-            health_packs,
-            message: None,
-            message_timer: 0.0,
-            damage_cooldown: 0.0,
-            game_time: 0.0,
-            paused: false,
+//把录制好的录像写入文件（只有通过 --record 指定了路径时才会录制）
+fn save_recording(recording: &mut Option<Replay>, path: &Option<String>) {
+    if let (Some(replay), Some(path)) = (recording.take(), path) {
+        match replay.save(path) {
+            Ok(()) => println!("录像已保存到 {}", path),
+            Err(e) => eprintln!("{}", e),
         }
     }
-
-
-//游戏主页面暂停按钮的渲染
-pub fn draw_pause_button(&mut self) {
-    let label = if self.paused { "Restart" } else { "Pause" };
-
-    let button_width = 160.0;
-    let button_height = 60.0;
-
-
-    let button_pos = Vec2::new(
-        TILE_SIZE * (MAP_WIDTH as f32 - 2.55),  
-        TILE_SIZE * 0.04 ,
-    );
-
-    if draw_button(
-        button_pos,
-        Vec2::new(button_width, button_height),
-        label,
-        Some(28),
-    ) {
-        self.paused = !self.paused;
-    }
 }
-    //绘制游戏画面的方法
-    pub fn draw(&mut self) {
-        for y in 0..MAP_HEIGHT {
-            for x in 0..MAP_WIDTH {
-                let pos_x = x as f32 * TILE_SIZE;
-                let pos_y = y as f32 * TILE_SIZE;
-
-                let texture = match self.map[y][x] {
-                    Tile::Wall => &self.wall_texture,
-                    Tile::Floor => &self.floor_texture,
-                };
-
-                draw_texture(texture, pos_x, pos_y, WHITE);
-            }
-        }
-        //绘制玩家
-        draw_texture(
-            &self.player.texture,
-            self.player.x as f32 * TILE_SIZE,
-            self.player.y as f32 * TILE_SIZE,
-            WHITE,
-        );
-
-        self.monsters.draw();//绘制怪物
-        self.health_packs.draw();//绘制血包
-        self.draw_pause_button(); //绘制暂停按钮
-        //如果有游戏提示信息，绘制信息
-        if let Some((msg, color)) = &self.message {
-            draw_message(msg, *color);
-        }
-    }
-
-    //更新游戏状态的函数（游戏的本质就是一个状态机）
-    pub fn update(&mut self, delta: f32) -> Option<(f32, usize, usize)> {
-        //游戏如果暂停，则状态不更新
-        if self.paused {
-            return None; 
-        }
-
-        //在本游戏里，玩家生存的时间是衡量玩家游戏情况的硬指标，因此需要计时
-        self.game_time += delta;
-
-        // 如果玩家生命值为 0，返回游戏时间、收集的血包数量和被攻击次数
-        if self.player.health <= 0 {
-            return Some((
-                self.game_time,
-                self.player.health_packs_collected,
-                self.player.monster_attacks,
-            ));
-        }
-
-        if self.damage_cooldown > 0.0 {
-            self.damage_cooldown -= delta;
-        }
-
-        //玩家上下左右四个方向的移动（必须移动到地板，不可以移动到墙壁）
-        if is_key_pressed(KeyCode::Left) && self.player.x > 0 {
-            if self.map[self.player.y][self.player.x - 1] == Tile::Floor {
-                self.player.x -= 1;
-            }
-        }
-        if is_key_pressed(KeyCode::Right) && self.player.x < MAP_WIDTH - 1 {
-            if self.map[self.player.y][self.player.x + 1] == Tile::Floor {
-                self.player.x += 1;
-            }
-        }
-        if is_key_pressed(KeyCode::Up) && self.player.y > 0 {
-            if self.map[self.player.y - 1][self.player.x] == Tile::Floor {
-                self.player.y -= 1;
-            }
-        }
-        if is_key_pressed(KeyCode::Down) && self.player.y < MAP_HEIGHT - 1 {
-            if self.map[self.player.y + 1][self.player.x] == Tile::Floor {
-                self.player.y += 1;
-            }
-        }
-
-        self.monsters.update(delta, &self.map);
-        self.health_packs.update(delta, &self.map);
-
-        if self.damage_cooldown <= 0.0 {
-            for monster in &self.monsters.list {
-                let mx = monster.x.floor() as usize;
-                let my = monster.y.floor() as usize;
-
-                // 如果玩家和怪物重叠，玩家受到伤害并设置冷却时间和提示消息
-                if mx == self.player.x && my == self.player.y {
-                    self.player.take_damage(20);
-                    self.damage_cooldown = 1.0;
-                    self.message = Some(("Attacked! -20HP".to_string(), RED));
-                    self.message_timer = 1.0;
-                    break;
-                }
-            }
-        }
-
-        if is_key_pressed(KeyCode::Space) {
-            if let Some(idx) = self.health_packs.list.iter().position(|hp| {
-                hp.x == self.player.x && hp.y == self.player.y
-            }) {
-                //玩家生命值满了，输出提示信息提示玩家
-                if self.player.health == 100 {
-                    self.message = Some(("Energy is already full!".to_string(), GREEN));
-                    self.message_timer = 2.0;
-                } else {
-                    //玩家生命值未满并且玩家与血包重叠，玩家回血，并将血包移除
-                    self.player.heal(20);
-                    self.health_packs.list.remove(idx);
-                }
-            }
-        }
 
-        if self.message.is_some() {
-            self.message_timer -= delta;
-            if self.message_timer <= 0.0 {
-                self.message = None;
-                self.message_timer = 0.0;//如果消息显示时间到，则该信息需要移除
-            }
-        }
-
-        None//游戏没结束，则返回None
+//从键盘和鼠标收集这一帧的输入快照，交给模拟层使用
+fn read_input(pause_clicked: bool) -> Input {
+    Input {
+        left: is_key_pressed(KeyCode::Left),
+        right: is_key_pressed(KeyCode::Right),
+        up: is_key_pressed(KeyCode::Up),
+        down: is_key_pressed(KeyCode::Down),
+        pick_up: is_key_pressed(KeyCode::Space),
+        attack: is_key_pressed(KeyCode::X),
+        toggle_pause: pause_clicked,
     }
 }
 
 #[macroquad::main("Monster Is Coming!")]
 async fn main() {
-    request_new_screen_size(MAP_WIDTH as f32 * TILE_SIZE, MAP_HEIGHT as f32 * TILE_SIZE);
+    //这次运行使用的固定关卡
+    let level = level_from_args();
+    //玩家、拾取物、怪物和难度的数值
+    let definitions = definitions_from_args();
+    //没有指定关卡文件时，菜单默认选中每局随机生成的地牢
+    let default_dungeon = if arg_value("--level").is_none() { Some(DungeonStyle::Rooms) } else { None };
+    //窗口大小根据地图的大小决定
+    let (map_width, map_height) = match default_dungeon {
+        Some(_) => (DUNGEON_WIDTH, DUNGEON_HEIGHT),
+        None => (level.map.width(), level.map.height()),
+    };
+    let window_width = (map_width as f32 * TILE_SIZE).clamp(MIN_WINDOW_SIZE.0, MAX_WINDOW_SIZE.0);
+    let window_height = (map_height as f32 * TILE_SIZE).clamp(MIN_WINDOW_SIZE.1, MAX_WINDOW_SIZE.1);
+    request_new_screen_size(window_width, window_height);
     next_frame().await;
 
     //枚举定义游戏状态
     enum GameState {
         Menu(Menu),
-        Playing(Game),
+        Playing(Box<Game>),
         GameOver(GameOver),
     }
-    //游戏的初始化状态是菜单
-    let mut game_state = GameState::Menu(Menu::new().await);
+    //命令行指定的随机种子
+    let cli_seed = seed_from_args();
+    //录像文件的保存路径，用法：rust_final --record run.json
+    let record_path = arg_value("--record");
+    //正在录制的录像
+    let mut recording: Option<Replay> = None;
+    //正在播放的录像，播放时用录像中的输入代替键盘
+    let mut playback: Option<ReplayPlayer> = None;
+    //每秒的模拟次数
+    let tick_rate = tick_rate_from_args();
+    //固定步长的时钟，游戏逻辑按固定步长推进，与帧率无关
+    let mut timestep = FixedTimestep::new(tick_rate);
+    //两次模拟之间累积的键盘输入
+    let mut pending_input = Input::default();
+
+    //用法：rust_final --replay run.json 直接播放录像，否则游戏的初始化状态是菜单
+    let mut game_state = match arg_value("--replay").map(|path| Replay::load(&path)) {
+        Some(Ok(replay)) => {
+            let stats = replay.simulate();
+            println!(
+                "录像结果: 时间 {:.2}s, 血包 {}, 被攻击 {} 次, 击杀 {}, 打败首领 {}, 最高波次 {}, 得分 {}",
+                stats.game_time,
+                stats.health_packs_collected,
+                stats.monster_attacks,
+                stats.kills,
+                stats.boss_defeated,
+                stats.best_wave,
+                stats.score.total
+            );
+            let game = replay.new_game();
+            //按录制时的步长播放
+            timestep = FixedTimestep::new(replay.tick_rate);
+            playback = Some(ReplayPlayer::new(replay));
+            GameState::Playing(Box::new(game))
+        }
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            GameState::Menu(Menu::new(cli_seed, default_dungeon, &level.name, &definitions).await)
+        }
+        None => GameState::Menu(Menu::new(cli_seed, default_dungeon, &level.name, &definitions).await),
+    };
+    //游戏画面的渲染器（所有图片只加载一次）
+    let renderer = Renderer::new(&definitions).await;
+    //跟随玩家的镜头
+    let mut camera = match &game_state {
+        GameState::Playing(game) => Camera::new(camera_target(game, 0.0), &game.map),
+        _ => Camera::new(Vec2::ZERO, &level.map),
+    };
     //游戏初始化难度为简单
     let mut _difficulty = GameDifficulty::Easy; 
+    //解锁过的成就，以及等着显示的成就提示和它已经显示的时间（第一个正在显示）
+    let mut achievements = Achievements::load_or_recover(ACHIEVEMENTS_PATH);
+    let mut toasts: Vec<(Achievement, f32)> = Vec::new();
+    //正在玩的每日挑战，结束时把成绩记到这一天
+    let mut daily_run: Option<DailyChallenge> = None;
 
     loop {
         clear_background(BLACK);
 
-        //帧间隔时间
+        //帧间隔时间（只用来推进固定步长的时钟）
         let delta = get_frame_time();
 
         //游戏的状态
@@ -341,21 +218,157 @@ async fn main() {
             GameState::Menu(menu) => {
                 //绘制菜单
                 menu.draw();
-                if let Some(selected_difficulty) = menu.update() {
-                    _difficulty = selected_difficulty;
-                    //选择了难度之后，进入游戏界面
-                    game_state = GameState::Playing(Game::new(_difficulty).await);
+                match menu.update() {
+                    Some(MenuChoice::NewRun(settings)) => {
+                        _difficulty = settings.difficulty;
+                        let seed = settings.seed;
+                        //选择了随机地牢时用种子生成地图，否则使用固定的关卡
+                        let run_level = match settings.dungeon {
+                            Some(style) => dungeon::generate(style, seed),
+                            None => level.clone(),
+                        };
+                        //自定义难度或者每日挑战的规则覆盖在定义文件的数值之上
+                        let run_definitions = match (&settings.custom, &settings.daily) {
+                            (Some(custom), _) => custom.apply(&definitions),
+                            (None, Some(daily)) => daily.apply(&definitions),
+                            (None, None) => definitions.clone(),
+                        };
+                        daily_run = settings.daily;
+                        if record_path.is_some() {
+                            recording = Some(Replay::new(seed, _difficulty, run_level.clone(), run_definitions.clone(), tick_rate));
+                        }
+                        timestep = FixedTimestep::new(tick_rate);
+                        pending_input = Input::default();
+                        //游戏中不读字符，清空菜单里留下的字符，免得它们一直留到结束时输入名字
+                        clear_input_queue();
+                        //选择了难度之后，进入游戏界面
+                        let game = Game::new(_difficulty, seed, &run_level, &run_definitions);
+                        camera = Camera::new(camera_target(&game, 0.0), &game.map);
+                        game_state = GameState::Playing(Box::new(game));
+                    }
+                    Some(MenuChoice::Continue(save)) => {
+                        //存档读出来之后就删掉，同一局只能继续一次
+                        save::delete(SAVE_PATH);
+                        let save = *save;
+                        _difficulty = save.game.difficulty;
+                        daily_run = save.daily.map(DailyChallenge::for_date);
+                        //录像要从第一步开始录，继续的这一局不录像
+                        recording = None;
+                        //按存档时的步长接着模拟
+                        timestep = FixedTimestep::new(save.tick_rate);
+                        pending_input = Input::default();
+                        clear_input_queue();
+                        camera = Camera::new(camera_target(&save.game, 0.0), &save.game.map);
+                        game_state = GameState::Playing(Box::new(save.game));
+                    }
+                    None => {}
                 }
             }
             GameState::Playing(game) => {
-                //绘制游戏界面
-                game.draw();
+                //镜头平滑地跟随玩家
+                camera.update(camera_target(game, timestep.alpha()), &game.map, delta);
+                //绘制游戏界面（在上一步和这一步模拟之间插值）
+                renderer.draw(game, timestep.alpha(), &camera);
+                //绘制暂停按钮
+                let pause_clicked = draw_pause_button(game.paused);
+                //暂停时可以存档退出（播放录像时没有这个按钮）
+                let save_clicked = game.paused && playback.is_none() && draw_save_quit_button();
+                //如果有游戏提示信息，绘制信息
+                if let Some((msg, color)) = &game.message {
+                    draw_message(msg, *color);
+                }
                 //绘制玩家血条
-                draw_health_bar(game.player.health);
+                draw_health_bar(game.player.health, game.player.max_health);
+                //绘制当前的波次
+                draw_wave(game.waves.wave);
+                //动态难度时显示当前的压力
+                if let Some(director) = &game.dynamic {
+                    draw_pressure(director.pressure);
+                }
+                //首领出场之后在玩家血条下面绘制首领的血条
+                if let Some((health, max_health)) = game.boss_health() {
+                    draw_boss_health_bar(health, max_health);
+                }
+
+                //这一帧的键盘输入先累积起来，留给下一步模拟使用
+                if playback.is_none() {
+                    pending_input.merge(read_input(pause_clicked));
+                }
 
-                if let Some((time, packs, attacks)) = game.update(delta) {
+                //按固定步长推进游戏逻辑，这一帧可能模拟0步、1步或多步
+                let mut finished = None;
+                for _ in 0..timestep.advance(delta) {
+                    //播放录像时从录像中取输入，否则使用累积的键盘输入
+                    let input = match &mut playback {
+                        Some(player) => match player.next_input() {
+                            Some(input) => input,
+                            None => {
+                                //录像播放完毕，直接进入游戏结束界面
+                                finished = Some(game.stats());
+                                break;
+                            }
+                        },
+                        None => std::mem::take(&mut pending_input),
+                    };
+                    if let Some(replay) = &mut recording {
+                        replay.record(input);
+                    }
+
+                    let game_over = game.update(&input, timestep.step()).into_iter().find_map(|event| match event {
+                        GameEvent::GameOver(stats) => Some(stats),
+                        _ => None,
+                    });
+                    if game_over.is_some() {
+                        finished = game_over;
+                        break;
+                    }
+                }
+
+                //把这一局写进存档，回到菜单
+                if save_clicked {
+                    match save::save(SAVE_PATH, game, timestep.tick_rate(), daily_run.as_ref().map(|daily| daily.date)) {
+                        Ok(()) => {
+                            save_recording(&mut recording, &record_path);
+                            daily_run = None;
+                            game_state = GameState::Menu(Menu::new(cli_seed, default_dungeon, &level.name, &definitions).await);
+                            next_frame().await;
+                            continue;
+                        }
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            game.message = Some(("Could not save the run".to_string(), RED));
+                        }
+                    }
+                }
+
+                //检查这一局有没有达成新的成就（播放录像和自定义难度不算）
+                if playback.is_none() && _difficulty != GameDifficulty::Custom {
+                    let stats = finished.unwrap_or_else(|| game.stats());
+                    let unlocked = achievements.update(&stats, Date::today());
+                    if !unlocked.is_empty() {
+                        if let Err(e) = achievements.save(ACHIEVEMENTS_PATH) {
+                            eprintln!("{}", e);
+                        }
+                        toasts.extend(unlocked.into_iter().map(|achievement| (achievement, 0.0)));
+                    }
+                }
+
+                if let Some(stats) = finished {
+                    save_recording(&mut recording, &record_path);
+                    //每日挑战的成绩记到这一天的记录里
+                    let daily = daily_run.take();
+                    if let Some(daily) = daily.as_ref().filter(|_| playback.is_none()) {
+                        let mut history = DailyHistory::load_or_recover(DAILY_HISTORY_PATH);
+                        history.record(daily.date, &stats);
+                        if let Err(e) = history.save(DAILY_HISTORY_PATH) {
+                            eprintln!("{}", e);
+                        }
+                    }
+                    //播放录像和每日挑战（规则不一样）的结果不记录到排行榜
+                    let ranked_difficulty = if playback.is_some() || daily.is_some() { None } else { Some(_difficulty) };
+                    playback = None;
                     //如果游戏结束，进入游戏结束界面
-                    game_state = GameState::GameOver(GameOver::new(time, packs, attacks).await);
+                    game_state = GameState::GameOver(GameOver::new(stats, ranked_difficulty).await);
                 }
             }
             GameState::GameOver(game_over) => {
@@ -363,11 +376,20 @@ async fn main() {
                 game_over.draw();
                 //返回菜单
                 if game_over.update() {
-                    game_state = GameState::Menu(Menu::new().await);
+                    game_state = GameState::Menu(Menu::new(cli_seed, default_dungeon, &level.name, &definitions).await);
                 }
             }
         }
 
+        //成就解锁的提示一个一个地显示，不管当前在哪个界面
+        if let Some((achievement, age)) = toasts.first_mut() {
+            draw_achievement_toast(achievement.title(), achievement.description(), *age);
+            *age += delta;
+            if *age >= TOAST_TIME {
+                toasts.remove(0);
+            }
+        }
+
         next_frame().await;
     }
 }
\ No newline at end of file
@@ -0,0 +1,168 @@
+//地图模块：运行时决定大小的格子地图，地图的大小来自关卡文件，不再是编译期常量
+use serde::{Deserialize, Serialize};
+
+//地图的格子有两种类型：一种是墙壁、一种是地板
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Tile {
+    Wall,
+    Floor,
+}
+
+//上下左右四个方向
+pub const DIRECTIONS_4: [(isize, isize); 4] = [(0, -1), (0, 1), (-1, 0), (1, 0)];
+//上下左右加上四个斜方向（"米"字形）
+pub const DIRECTIONS_8: [(isize, isize); 8] = [
+    (0, -1),
+    (0, 1),
+    (-1, 0),
+    (1, 0),
+    (-1, -1),
+    (1, 1),
+    (-1, 1),
+    (1, -1),
+];
+
+//地图结构体：按行存放所有格子
+//从存档中读取时先检查格子的数量和宽高对得上，否则按坐标取格子时会越界
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "MapData")]
+pub struct Map {
+    width: usize,
+    height: usize,
+    tiles: Vec<Tile>,
+}
+
+//存档中的原始地图数据，检查之后才能变成 Map
+#[derive(Deserialize)]
+struct MapData {
+    width: usize,
+    height: usize,
+    tiles: Vec<Tile>,
+}
+
+impl TryFrom<MapData> for Map {
+    type Error = String;
+
+    fn try_from(data: MapData) -> Result<Self, String> {
+        if data.width.checked_mul(data.height) != Some(data.tiles.len()) {
+            return Err(format!("地图应有 {}x{} 个格子，实际有 {} 个", data.width, data.height, data.tiles.len()));
+        }
+        Ok(Self {
+            width: data.width,
+            height: data.height,
+            tiles: data.tiles,
+        })
+    }
+}
+
+impl Map {
+    //创建一张所有格子都是同一种类型的地图
+    pub fn new(width: usize, height: usize, fill: Tile) -> Self {
+        Self {
+            width,
+            height,
+            tiles: vec![fill; width * height],
+        }
+    }
+
+    //创建一张四周是墙壁、中间是地板的地图
+    pub fn walled(width: usize, height: usize) -> Self {
+        let mut map = Self::new(width, height, Tile::Floor);
+        for y in 0..height {
+            for x in 0..width {
+                if x == 0 || x == width - 1 || y == 0 || y == height - 1 {
+                    map.set(x, y, Tile::Wall);
+                }
+            }
+        }
+        map
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    //坐标是否在地图范围内
+    pub fn in_bounds(&self, x: isize, y: isize) -> bool {
+        x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height
+    }
+
+    //读取一个格子，越界时返回 None
+    pub fn get(&self, x: usize, y: usize) -> Option<Tile> {
+        if x < self.width && y < self.height {
+            Some(self.tiles[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    //修改一个格子（越界时忽略）
+    pub fn set(&mut self, x: usize, y: usize, tile: Tile) {
+        if x < self.width && y < self.height {
+            self.tiles[y * self.width + x] = tile;
+        }
+    }
+
+    //坐标处是否是地板（越界视为不是地板）
+    pub fn is_floor(&self, x: usize, y: usize) -> bool {
+        self.get(x, y) == Some(Tile::Floor)
+    }
+
+    //(x, y) 沿 (dx, dy) 方向的相邻格子，越界时返回 None
+    pub fn neighbour(&self, x: usize, y: usize, dx: isize, dy: isize) -> Option<(usize, usize)> {
+        let nx = x as isize + dx;
+        let ny = y as isize + dy;
+        if self.in_bounds(nx, ny) {
+            Some((nx as usize, ny as usize))
+        } else {
+            None
+        }
+    }
+
+    //(x, y) 在地图范围内的所有相邻格子，diagonal 为 true 时包括四个斜方向
+    pub fn neighbours(&self, x: usize, y: usize, diagonal: bool) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let directions: &[(isize, isize)] = if diagonal { &DIRECTIONS_8 } else { &DIRECTIONS_4 };
+        directions.iter().filter_map(move |&(dx, dy)| self.neighbour(x, y, dx, dy))
+    }
+
+    //从 (x, y) 出发只走上下左右能到达的所有地板格子
+    pub fn reachable_from(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let mut visited = vec![false; self.width * self.height];
+        let mut reached = Vec::new();
+        if !self.is_floor(x, y) {
+            return reached;
+        }
+        let mut stack = vec![(x, y)];
+        visited[y * self.width + x] = true;
+        while let Some((cx, cy)) = stack.pop() {
+            reached.push((cx, cy));
+            for (nx, ny) in self.neighbours(cx, cy, false) {
+                if self.is_floor(nx, ny) && !visited[ny * self.width + nx] {
+                    visited[ny * self.width + nx] = true;
+                    stack.push((nx, ny));
+                }
+            }
+        }
+        reached
+    }
+
+    //按行遍历所有格子，返回 (x, y, 格子)
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, Tile)> + '_ {
+        self.tiles
+            .iter()
+            .enumerate()
+            .map(move |(i, tile)| (i % self.width, i / self.width, *tile))
+    }
+
+    //所有地板格子的坐标
+    pub fn floor_tiles(&self) -> Vec<(usize, usize)> {
+        self.iter()
+            .filter(|&(_, _, tile)| tile == Tile::Floor)
+            .map(|(x, y, _)| (x, y))
+            .collect()
+    }
+}
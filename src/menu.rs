@@ -1,42 +1,241 @@
 use macroquad::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::dungeon::DungeonStyle;
+use crate::custom::CustomDifficulty;
+use crate::custom_editor::{CustomEditor, EditorAction};
+use crate::definitions::Definitions;
+use crate::high_score_screen::HighScoreScreen;
+use crate::achievement_screen::AchievementScreen;
+use crate::daily::{DailyChallenge, DailyHistory, DAILY_HISTORY_PATH};
+use crate::date::Date;
+use crate::save::{self, SaveFile, SAVE_PATH};
 
-
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 //游戏难度不同，怪物数量不同，怪物越多，玩家生存的难度越大
 pub enum GameDifficulty {
     Easy,
     Medium,
     Hard,
+    //根据玩家的表现自动调整
+    Dynamic,
+    //玩家在菜单里自己设定数值
+    Custom,
+}
+
+//所有的难度（定义文件必须给每一个难度都写上预设）
+pub const ALL_DIFFICULTIES: [GameDifficulty; 5] = [
+    GameDifficulty::Easy,
+    GameDifficulty::Medium,
+    GameDifficulty::Hard,
+    GameDifficulty::Dynamic,
+    GameDifficulty::Custom,
+];
+
+//玩家在菜单中选定的这一局的设置
+#[derive(Clone)]
+pub struct RunSettings {
+    pub difficulty: GameDifficulty,
+    pub seed: u64,
+    //用种子生成的地牢风格，为 None 时使用固定的关卡（默认场地或 --level 指定的关卡文件）
+    pub dungeon: Option<DungeonStyle>,
+    //自定义难度的数值，只有选择 Custom 难度时才有
+    pub custom: Option<CustomDifficulty>,
+    //每日挑战，只有玩每日挑战时才有（难度、种子和地图都由它决定）
+    pub daily: Option<DailyChallenge>,
+}
+
+//玩家在菜单中的选择：开始新的一局，或者继续存档里的那一局
+pub enum MenuChoice {
+    NewRun(RunSettings),
+    Continue(Box<SaveFile>),
+}
+
+//菜单当前显示的界面
+enum MenuView {
+    //选择难度、种子和地图的主界面
+    Main,
+    //自定义难度的编辑界面
+    Custom,
+    //排行榜
+    HighScores(HighScoreScreen),
+    //成就
+    Achievements(AchievementScreen),
 }
 
 pub struct Menu {
     background: Texture2D,
     selected_difficulty: GameDifficulty,
+    //玩家输入的随机种子（只包含数字），为空时每局使用一个随机的种子
+    seed_text: String,
+    //选定的地图：随机生成的地牢，或者固定的关卡
+    selected_dungeon: Option<DungeonStyle>,
+    //固定关卡的名字
+    level_name: String,
+    //自定义难度的编辑界面（关掉之后保留编辑过的数值）
+    editor: CustomEditor,
+    view: MenuView,
+    //今天的每日挑战，以及菜单上显示的今天的最高分和连续挑战的天数
+    daily: DailyChallenge,
+    daily_best: Option<u32>,
+    streak: usize,
+    //是否有可以继续的存档，以及读取存档失败时显示的提示
+    has_save: bool,
+    save_error: Option<&'static str>,
+}
+
+//date 这一天的每日挑战的最高分，以及到这一天为止连续挑战的天数
+fn daily_record(date: Date) -> (Option<u32>, usize) {
+    let history = DailyHistory::load_or_recover(DAILY_HISTORY_PATH);
+    (history.result(date).map(|r| r.best_score), history.streak(date))
 }
 
 impl Menu {
-    pub async fn new() -> Self {
+    //seed 为命令行指定的种子，会预先填入菜单
+    //dungeon 为默认选中的地图，level_name 为固定关卡的名字，definitions 为自定义难度的初始数值
+    pub async fn new(seed: Option<u64>, dungeon: Option<DungeonStyle>, level_name: &str, definitions: &Definitions) -> Self {
         let background = load_texture("assets/menu_background.png").await.unwrap();
         
+        let daily = DailyChallenge::today();
+        let (daily_best, streak) = daily_record(daily.date);
+
         //游戏是默认简单难度的
         Self {
             background,
             selected_difficulty: GameDifficulty::Easy, 
+            seed_text: seed.map(|s| s.to_string()).unwrap_or_default(),
+            selected_dungeon: dungeon,
+            level_name: level_name.to_string(),
+            editor: CustomEditor::new(definitions),
+            view: MenuView::Main,
+            daily_best,
+            streak,
+            daily,
+            has_save: save::exists(SAVE_PATH),
+            save_error: None,
+        }
+    }
+
+    //用当前的选择生成这一局的设置
+    fn settings(&self, custom: Option<CustomDifficulty>) -> RunSettings {
+        //没有输入种子时随机选一个，游戏结束界面会显示它
+        let seed = self.seed_text.parse().unwrap_or_else(|_| ::rand::random());
+        RunSettings {
+            difficulty: self.selected_difficulty,
+            seed,
+            dungeon: self.selected_dungeon,
+            custom,
+            daily: None,
         }
     }
 
-    pub fn update(&mut self) -> Option<GameDifficulty> {
+    //返回玩家的选择：新的一局的设置，或者读出来的存档
+    pub fn update(&mut self) -> Option<MenuChoice> {
+        //菜单一直开着的时候过了午夜，换成新一天的每日挑战
+        let today = Date::today();
+        if today != self.daily.date {
+            self.daily = DailyChallenge::for_date(today);
+            (self.daily_best, self.streak) = daily_record(self.daily.date);
+        }
+
+        //打开了编辑界面、排行榜或者成就列表时，输入都交给它们
+        match &mut self.view {
+            MenuView::Main => {}
+            MenuView::Custom => {
+                match self.editor.update() {
+                    Some(EditorAction::Start(custom)) => return Some(MenuChoice::NewRun(self.settings(Some(custom)))),
+                    Some(EditorAction::Back) => self.view = MenuView::Main,
+                    None => {}
+                }
+                return None;
+            }
+            MenuView::HighScores(screen) => {
+                if screen.update() {
+                    self.view = MenuView::Main;
+                }
+                return None;
+            }
+            MenuView::Achievements(screen) => {
+                if screen.update() {
+                    self.view = MenuView::Main;
+                }
+                return None;
+            }
+        }
+
+        //打开排行榜，先显示选中的难度
+        if is_key_pressed(KeyCode::H) {
+            self.view = MenuView::HighScores(HighScoreScreen::new(self.selected_difficulty));
+            return None;
+        }
+
+        //开始今天的每日挑战
+        if is_key_pressed(KeyCode::D) {
+            return Some(MenuChoice::NewRun(RunSettings {
+                difficulty: self.daily.difficulty,
+                seed: self.daily.seed,
+                dungeon: Some(self.daily.dungeon),
+                custom: None,
+                daily: Some(self.daily.clone()),
+            }));
+        }
+
+        //继续存档里的那一局；存档读不出来时打印原因，菜单上只显示简短的提示，存档文件原样保留
+        if self.has_save && is_key_pressed(KeyCode::C) {
+            match save::load(SAVE_PATH) {
+                Ok(Some(save)) => return Some(MenuChoice::Continue(Box::new(save))),
+                Ok(None) => self.save_error = Some("The saved run is gone"),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    self.save_error = Some("The saved run is from an incompatible version or damaged");
+                }
+            }
+            self.has_save = false;
+            return None;
+        }
+
+        //打开成就列表
+        if is_key_pressed(KeyCode::A) {
+            self.view = MenuView::Achievements(AchievementScreen::load());
+            return None;
+        }
+
+        // 种子的输入：数字键追加一位，退格键删除一位
+        while let Some(c) = get_char_pressed() {
+            if c.is_ascii_digit() && self.seed_text.len() < 19 {
+                self.seed_text.push(c);
+            }
+        }
+        if is_key_pressed(KeyCode::Backspace) {
+            self.seed_text.pop();
+        }
+
         // 难度的选择
         if is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::Right) {
             self.selected_difficulty = match self.selected_difficulty {
                 GameDifficulty::Easy => GameDifficulty::Medium,
                 GameDifficulty::Medium => GameDifficulty::Hard,
-                GameDifficulty::Hard => GameDifficulty::Easy,
+                GameDifficulty::Hard => GameDifficulty::Dynamic,
+                GameDifficulty::Dynamic => GameDifficulty::Custom,
+                GameDifficulty::Custom => GameDifficulty::Easy,
+            };
+        }
+
+        // 地图的选择
+        if is_key_pressed(KeyCode::Up) || is_key_pressed(KeyCode::Down) {
+            self.selected_dungeon = match self.selected_dungeon {
+                Some(DungeonStyle::Rooms) => Some(DungeonStyle::Caves),
+                Some(DungeonStyle::Caves) => None,
+                None => Some(DungeonStyle::Rooms),
             };
         }
 
         if is_key_pressed(KeyCode::Enter) {
-            return Some(self.selected_difficulty);
+            //自定义难度先打开编辑界面，在编辑界面里开始游戏
+            if self.selected_difficulty == GameDifficulty::Custom {
+                self.view = MenuView::Custom;
+                return None;
+            }
+            return Some(MenuChoice::NewRun(self.settings(None)));
         }
 
         None
@@ -45,12 +244,29 @@ impl Menu {
     pub fn draw(&self) {
         // step1--绘制背景
         draw_texture(&self.background, 0.0, 0.0, WHITE);
+        match &self.view {
+            MenuView::Main => {}
+            MenuView::Custom => {
+                self.editor.draw();
+                return;
+            }
+            MenuView::HighScores(screen) => {
+                screen.draw();
+                return;
+            }
+            MenuView::Achievements(screen) => {
+                screen.draw();
+                return;
+            }
+        }
 
         // step2---显式难度的文字
         let difficulty_text = match self.selected_difficulty {
             GameDifficulty::Easy => "Easy (1 Monster)",
             GameDifficulty::Medium => "Medium (3 Monsters)",
             GameDifficulty::Hard => "Hard (5 Monsters)",
+            GameDifficulty::Dynamic => "Dynamic (adapts to how you play)",
+            GameDifficulty::Custom => "Custom (press ENTER to edit)",
         };
         let diff_size = 30.0;
         let diff_width = measure_text(difficulty_text, None, diff_size as u16, 1.0).width;
@@ -71,6 +287,79 @@ impl Menu {
             GREEN,
         );
 
+        // step3.5---显示随机种子
+        let seed_text = if self.seed_text.is_empty() {
+            "Seed: random (type digits to set)".to_string()
+        } else {
+            format!("Seed: {}", self.seed_text)
+        };
+        let seed_size = 24.0;
+        let seed_width = measure_text(&seed_text, None, seed_size as u16, 1.0).width;
+        draw_text(
+            &seed_text,
+            screen_width() / 2.0 - seed_width / 2.0 + 1.0,
+            screen_height() / 2.0 + 40.0 + 1.0,
+            seed_size,
+            BLACK,
+        );
+        draw_text(
+            &seed_text,
+            screen_width() / 2.0 - seed_width / 2.0,
+            screen_height() / 2.0 + 40.0,
+            seed_size,
+            WHITE,
+        );
+
+        // step3.6---显示选定的地图
+        let map_text = match self.selected_dungeon {
+            Some(style) => format!("Map: {} (random dungeon)", style.name()),
+            None => format!("Map: {}", self.level_name),
+        };
+        let map_size = 24.0;
+        let map_width = measure_text(&map_text, None, map_size as u16, 1.0).width;
+        draw_text(
+            &map_text,
+            screen_width() / 2.0 - map_width / 2.0 + 1.0,
+            screen_height() / 2.0 + 75.0 + 1.0,
+            map_size,
+            BLACK,
+        );
+        draw_text(
+            &map_text,
+            screen_width() / 2.0 - map_width / 2.0,
+            screen_height() / 2.0 + 75.0,
+            map_size,
+            WHITE,
+        );
+
+        // step3.7---显示今天的每日挑战和连续挑战的天数
+        let best_text = match self.daily_best {
+            Some(best) => format!("best {}", best),
+            None => "not played yet".to_string(),
+        };
+        let daily_text = format!("Daily Challenge (D): {}", self.daily.describe());
+        let streak_text = format!("{}  -  {}  -  streak {} day(s)", self.daily.date, best_text, self.streak);
+        for (i, text) in [daily_text, streak_text].iter().enumerate() {
+            let size = 22.0;
+            let width = measure_text(text, None, size as u16, 1.0).width;
+            let y = screen_height() / 2.0 + 115.0 + i as f32 * 26.0;
+            draw_text(text, screen_width() / 2.0 - width / 2.0 + 1.0, y + 1.0, size, BLACK);
+            draw_text(text, screen_width() / 2.0 - width / 2.0, y, size, GOLD);
+        }
+
+        // step3.8---有存档时提示可以继续，读取存档失败时显示原因
+        let save_text = match self.save_error {
+            Some(error) => Some((error, RED)),
+            None => self.has_save.then_some(("Press C to Continue your saved run", WHITE)),
+        };
+        if let Some((text, color)) = save_text {
+            let size = 22.0;
+            let width = measure_text(text, None, size as u16, 1.0).width;
+            let y = screen_height() - 130.0;
+            draw_text(text, screen_width() / 2.0 - width / 2.0 + 1.0, y + 1.0, size, BLACK);
+            draw_text(text, screen_width() / 2.0 - width / 2.0, y, size, color);
+        }
+
         // step4---显示游戏开始的文字提示
         let start_text = "Press ENTER to Begin";
         let start_size = 25.0;
@@ -92,7 +381,7 @@ impl Menu {
         );
 
         // step5---显示提示用户进行难度选择的文字
-        let hint_text = "Use LEFT/RIGHT to change difficulty";
+        let hint_text = "LEFT/RIGHT: difficulty    UP/DOWN: map    H: high scores    A: achievements";
         let hint_size = 20.0;
         let hint_width = measure_text(hint_text, None, hint_size as u16, 1.0).width;
         draw_text(
@@ -103,6 +392,18 @@ impl Menu {
             Color::new(0.4, 0.8, 1.0, 1.0), 
         );
 
+        //操作说明
+        let controls_hint = "Arrows: move    X: attack    SPACE: pick up";
+        let controls_size = 18.0;
+        let controls_width = measure_text(controls_hint, None, controls_size as u16, 1.0).width;
+        draw_text(
+            controls_hint,
+            screen_width() / 2.0 - controls_width / 2.0,
+            screen_height() - 72.0,
+            controls_size,
+            WHITE,
+        );
+
         // step6---显示提示用户游戏可进行暂停的文字
         let pause_hint = "Click the Pause button at the top-right corner to pause the game";
         let pause_size = 18.0;
@@ -1,123 +1,234 @@
-use macroquad::prelude::*;
 // 引入随机数生成器相关模块
-use ::rand::{Rng, SeedableRng};
-use ::rand::rngs::SmallRng;
-use crate::{TILE_SIZE, Tile, MAP_WIDTH, MAP_HEIGHT};
+use ::rand::Rng;
+use crate::rng::GameRng;
+use crate::level::Level;
+use crate::map::Map;
+use crate::pathfinding::{find_path, Connectivity};
+use crate::ai::{AiState, Perception};
+use crate::definitions::{Definitions, DifficultyPreset};
+use crate::fov::FieldOfView;
+use crate::projectile::Projectile;
+use crate::monster_kind::{MonsterKind, MonsterStats, MovementPattern};
+use serde::{Deserialize, Serialize};
+
+//寻路时被其他怪物占着的格子的额外代价（软障碍：能绕开就绕开）
+const CROWD_COST: u32 = 30;
 
 //单个怪物的结构体
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Monster {
+    //怪物的种类，决定它的图片
+    pub kind: MonsterKind,
+    //这种怪物的数值（速度、伤害、生命值、移动方式），来自定义文件
+    pub stats: MonsterStats,
     //怪物在地图的位置
     pub x: f32,
     pub y: f32,
-    //怪物的样子
-    pub texture: Texture2D,
+    //上一步模拟时怪物的位置，渲染时在两者之间插值
+    pub prev_x: f32,
+    pub prev_y: f32,
+    //怪物当前的生命值
+    pub health: i32,
+    //怪物当前的行为状态（切换状态用的参数在 stats.behaviour 中）
+    pub state: AiState,
+    //移动计时器，每种怪物的移动间隔不同，所以每个怪物单独计时
+    move_timer: f32,
+    //距离下一次可以射击还剩的时间（秒），只对有远程攻击的怪物有用
+    shot_timer: f32,
+}
+
+impl Monster {
+    //在格子 (x, y) 上创建一个怪物
+    pub fn new(kind: MonsterKind, stats: MonsterStats, (x, y): (usize, usize)) -> Self {
+        let (x, y) = (x as f32, y as f32);
+        Self {
+            kind,
+            x,
+            y,
+            prev_x: x,
+            prev_y: y,
+            health: stats.health,
+            stats,
+            state: AiState::Wander,
+            move_timer: 0.0,
+            shot_timer: 0.0,
+        }
+    }
+
+    //怪物所在的格子
+    pub fn tile(&self) -> (usize, usize) {
+        (self.x.floor() as usize, self.y.floor() as usize)
+    }
+}
+
+//怪物被玩家打中的结果
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HitOutcome {
+    //受伤但还活着
+    Hurt,
+    //被打死了
+    Killed(MonsterKind),
 }
 
 //怪物集合的结构体
-//结构体作用：管理游戏中的所有怪物，包含怪物列表、样子、随机数生成器、移动计时器和移动间隔等信息。
+//结构体作用：管理游戏中的所有怪物
+//随机数生成器由游戏统一持有并传入，保证同一个种子得到同样的一局游戏
+#[derive(Serialize, Deserialize)]
 pub struct Monsters {
     pub list: Vec<Monster>,
-    pub texture: Texture2D,
-    //随机数生成器
-    rng: SmallRng,
-    move_timer: f32,
-    move_interval: f32,
+    //一格一格走的怪物的移动方式（四方向或八方向，以及能否贴着墙角斜穿）
+    movement: Connectivity,
+    //所有怪物的速度倍率（动态难度会调整它），移动间隔除以这个倍率
+    pub speed_scale: f32,
 }
 
 impl Monsters {
-    //创建怪物集合实例的函数
-     pub async fn new(count: usize) -> Self {
-        let texture = load_texture("assets/monster.png").await.unwrap();
-        texture.set_filter(FilterMode::Nearest);
+    //创建怪物集合实例的函数，难度预设中的每一项生成一个对应种类的怪物
+    //怪物优先出生在关卡指定的出生点上，出生点不够用时再随机选择其他地板（不会出生在玩家脚下或紧挨着玩家）
+    pub fn new(preset: &DifficultyPreset, definitions: &Definitions, level: &Level, rng: &mut GameRng) -> Self {
+        let mut spawns = level.monster_spawns.clone();
+        let (px, py) = level.player_start;
+        let mut floors: Vec<(usize, usize)> = level
+            .map
+            .floor_tiles()
+            .into_iter()
+            .filter(|&(x, y)| x.abs_diff(px).max(y.abs_diff(py)) > 1)
+            .filter(|pos| !spawns.contains(pos))
+            .collect();
 
         let mut list = Vec::new();
-        let mut rng = SmallRng::from_entropy();
-
-        for _ in 0..count {
-            loop {
-                //随机生成怪物的坐标
-                let x = rng.gen_range(1..MAP_WIDTH - 1) as f32;
-                let y = rng.gen_range(1..MAP_HEIGHT - 1) as f32;
-
-                //检查新生成的怪物位置是否与已有的怪物重叠
-                if !list.iter().any(|m: &Monster| (m.x - x).abs() < 1.0 && (m.y - y).abs() < 1.0) {
-                    list.push(Monster {
-                        x,
-                        y,
-                        texture: texture.clone(),
-                    });
-                    break;
-                }
-            }
+        for &kind in &preset.monsters {
+            let pool = if !spawns.is_empty() {
+                &mut spawns
+            } else if !floors.is_empty() {
+                &mut floors
+            } else {
+                break;
+            };
+            //随机选取一个出生点（选过的出生点会被移除，所以怪物不会重叠）
+            let position = pool.swap_remove(rng.gen_range(0..pool.len()));
+            list.push(Monster::new(kind, definitions.monster(kind).clone(), position));
         }
 
         //返回Monsters实例
         Self {
             list,
-            texture,
-            rng,
-            move_timer: 0.0,
-            move_interval: 0.5,
+            movement: preset.movement,
+            speed_scale: 1.0,
         }
     }
 
-    pub fn update(&mut self, delta: f32, map: &[[Tile; MAP_WIDTH]; MAP_HEIGHT]) {
-        self.move_timer += delta;
-        //怪物每次移动必须要间隔一个移动间隔，如果时间短于移动间隔，则不移动
-        if self.move_timer < self.move_interval {
-            return;
-        }
-        //重置移动计时器
-        self.move_timer = 0.0;
-
-        //怪物可以移动的方向（“米”字形）
-        let directions = [
-            (0.0, -1.0),
-            (0.0, 1.0),
-            (-1.0, 0.0),
-            (1.0, 0.0),
-            (-1.0, -1.0),
-            (1.0, 1.0),
-            (-1.0, 1.0),
-            (1.0, -1.0),
-        ];
+    //在 position 处生成一个新的怪物
+    pub fn spawn(&mut self, kind: MonsterKind, stats: MonsterStats, position: (usize, usize)) {
+        self.list.push(Monster::new(kind, stats, position));
+    }
+
+    //格子 (x, y) 上是否有怪物
+    pub fn is_occupied(&self, x: usize, y: usize) -> bool {
+        self.list.iter().any(|m| m.tile() == (x, y))
+    }
 
+    //player 为玩家所在的格子，player_powered_up 表示玩家是否处于强化状态
+    //player_fov 为玩家的视野（视野是对称的，玩家能看见怪物也就意味着怪物能看见玩家）
+    //每个怪物先根据对玩家的感知切换行为状态，再按状态决定往哪里走；返回远程怪物这一步射出的弹丸
+    pub fn update(
+        &mut self,
+        delta: f32,
+        map: &Map,
+        player_fov: &FieldOfView,
+        player: (usize, usize),
+        player_powered_up: bool,
+        rng: &mut GameRng,
+    ) -> Vec<Projectile> {
         //获取当前所有怪物的位置
         let positions: Vec<(f32, f32)> = self.list.iter().map(|m| (m.x, m.y)).collect();
         let mut new_positions: Vec<(f32, f32)> = Vec::with_capacity(self.list.len());
+        let mut shots = Vec::new();
 
         for i in 0..self.list.len() {
-            let mut attempts = 0;
-            loop {
-                //随机选择一个移动方向
-                let dir = directions[self.rng.gen_range(0..directions.len())];
-                let new_x = positions[i].0 + dir.0;
-                let new_y = positions[i].1 + dir.1;
+            let stats = self.list[i].stats.clone();
+            let move_interval = stats.move_interval / self.speed_scale;
+            self.list[i].shot_timer -= delta;
+            self.list[i].move_timer += delta;
+            //怪物每次移动必须要间隔一个移动间隔，如果时间短于移动间隔，则不移动
+            if self.list[i].move_timer < move_interval {
+                continue;
+            }
+            //重置移动计时器
+            self.list[i].move_timer = 0.0;
 
-                //检查新位置是否越界
-                if new_x < 1.0 || new_x >= (MAP_WIDTH - 1) as f32 || new_y < 1.0 || new_y >= (MAP_HEIGHT - 1) as f32 {
-                    attempts += 1;
-                    if attempts > 10 { break; }
-                    continue;
-                }
+            let position = (positions[i].0 as usize, positions[i].1 as usize);
+            let monster = &self.list[i];
+            let perception = Perception {
+                player,
+                player_visible: can_see(player_fov, position, player, stats.behaviour.sight_radius),
+                player_powered_up,
+            };
+            let state = monster.state.next(&stats.behaviour, &perception, position, move_interval);
+            self.list[i].state = state;
 
-                //检查新位置是否是墙壁
-                if map[new_y as usize][new_x as usize] == Tile::Wall {
-                    attempts += 1;
-                    if attempts > 10 { break; }
-                    continue;
-                }
+            //追赶玩家的远程怪物和玩家在同一条直线上时，站在原地射击
+            if let (AiState::Chase { .. }, Some(ranged)) = (state, stats.ranged)
+                && self.list[i].shot_timer <= 0.0
+                && let Some(direction) = firing_direction(position, player, ranged.range)
+            {
+                shots.push(Projectile::new(position.0, position.1, direction, ranged.speed, ranged.damage, ranged.range));
+                self.list[i].shot_timer = ranged.cooldown;
+                continue;
+            }
 
-                //检查新位置是否与其他怪物重叠
-                let overlap = positions.iter().enumerate().any(|(j, &(x, y))| {
-                    if i == j { return false; }
-                    (x - new_x).abs() < 1.0 && (y - new_y).abs() < 1.0
-                }) || new_positions.iter().any(|&(x, y)| {
-                    (x - new_x).abs() < 1.0 && (y - new_y).abs() < 1.0
+            //沿最短路径向目标走，被其他怪物占着的格子代价更高；穿墙的怪物直接朝目标走
+            let path_towards = |target: (usize, usize)| -> Vec<(usize, usize)> {
+                let connectivity = match stats.movement {
+                    MovementPattern::Walk => self.movement,
+                    MovementPattern::Rook { .. } => Connectivity::Four,
+                    MovementPattern::Phase => {
+                        return self.phase_step_towards(map, position, target).into_iter().collect();
+                    }
+                };
+                let path = find_path(map, position, target, connectivity, |x, y| {
+                    let crowded = positions
+                        .iter()
+                        .enumerate()
+                        .any(|(j, &(mx, my))| j != i && mx as usize == x && my as usize == y);
+                    if crowded { CROWD_COST } else { 0 }
                 });
+                path.unwrap_or_default()
+            };
 
-                if !overlap {
+            //根据状态决定这一步想走的路线，游荡时随机乱走
+            let wanted = match state {
+                AiState::Chase { .. } => path_towards(player),
+                AiState::Search { target, .. } => path_towards(target),
+                AiState::Flee => self.flee_step(map, position, player, stats.movement).into_iter().collect(),
+                AiState::Wander => Vec::new(),
+            };
+            if let Some((x, y)) = self.follow(&wanted, position, stats.movement, &positions, &new_positions, i) {
+                self.list[i].x = x;
+                self.list[i].y = y;
+                new_positions.push((x, y));
+                continue;
+            }
+
+            //逃跑的怪物无路可逃时留在原地，不会乱走到玩家身边
+            if state == AiState::Flee {
+                continue;
+            }
+
+            //能走的相邻格子
+            let steps = self.steps(map, position, stats.movement);
+            if steps.is_empty() {
+                continue;
+            }
+
+            let mut attempts = 0;
+            loop {
+                //随机选择一个移动方向
+                let (x, y) = steps[rng.gen_range(0..steps.len())];
+                let (new_x, new_y) = (x as f32, y as f32);
+
+                //检查新位置是否与其他怪物重叠
+                if !overlaps(&positions, &new_positions, i, new_x, new_y) {
                     //更新怪物位置
                     self.list[i].x = new_x;
                     self.list[i].y = new_y;
@@ -129,17 +240,136 @@ impl Monsters {
                 }
             }
         }
+        shots
     }
 
-    //负责将所有怪物绘制到游戏主页面的函数
-    pub fn draw(&self) {
-        for monster in &self.list {
-            draw_texture(
-                &monster.texture,
-                monster.x * TILE_SIZE,
-                monster.y * TILE_SIZE,
-                WHITE,
-            );
+    //玩家攻击 target 格子上的怪物，那里没有怪物时返回 None
+    //活下来的怪物沿 direction 被击退 knockback 格（撞到墙壁或其他怪物就停下），并且要重新计时才能移动；被打死的怪物从列表中移除
+    pub fn hit(
+        &mut self,
+        map: &Map,
+        target: (usize, usize),
+        direction: (isize, isize),
+        damage: i32,
+        knockback: usize,
+    ) -> Option<HitOutcome> {
+        let i = self.list.iter().position(|m| m.tile() == target)?;
+
+        self.list[i].health -= damage;
+        if self.list[i].health <= 0 {
+            let monster = self.list.remove(i);
+            return Some(HitOutcome::Killed(monster.kind));
+        }
+
+        let mut position = target;
+        for _ in 0..knockback {
+            let Some(next) = map.neighbour(position.0, position.1, direction.0, direction.1) else {
+                break;
+            };
+            //穿墙的怪物可以被打进墙里，其他怪物撞到墙壁就停下
+            let passable = map.is_floor(next.0, next.1) || self.list[i].stats.movement == MovementPattern::Phase;
+            if !passable || self.is_occupied(next.0, next.1) {
+                break;
+            }
+            position = next;
         }
+        let monster = &mut self.list[i];
+        monster.x = position.0 as f32;
+        monster.y = position.1 as f32;
+        monster.move_timer = 0.0;
+        Some(HitOutcome::Hurt)
     }
+
+    //沿着路线走这一次的移动，返回最后停下的位置（第一步就被挡住时返回 None）
+    //一般的怪物只走一格；走直线的怪物沿着同一个方向最多滑行 slide 格，遇到拐弯或其他怪物就停下
+    fn follow(
+        &self,
+        path: &[(usize, usize)],
+        position: (usize, usize),
+        movement: MovementPattern,
+        positions: &[(f32, f32)],
+        new_positions: &[(f32, f32)],
+        i: usize,
+    ) -> Option<(f32, f32)> {
+        let max_steps = match movement {
+            MovementPattern::Rook { slide } => slide.max(1),
+            _ => 1,
+        };
+        let direction = |from: (usize, usize), to: (usize, usize)| {
+            (to.0 as isize - from.0 as isize, to.1 as isize - from.1 as isize)
+        };
+
+        let mut stop = None;
+        let mut previous = position;
+        for &step in path.iter().take(max_steps) {
+            if stop.is_some() && direction(previous, step) != direction(position, path[0]) {
+                break;
+            }
+            let (x, y) = (step.0 as f32, step.1 as f32);
+            if overlaps(positions, new_positions, i, x, y) {
+                break;
+            }
+            stop = Some((x, y));
+            previous = step;
+        }
+        stop
+    }
+
+    //从 position 能一步走到的格子
+    fn steps(&self, map: &Map, position: (usize, usize), movement: MovementPattern) -> Vec<(usize, usize)> {
+        match movement {
+            MovementPattern::Walk => self.movement.steps(map, position.0, position.1).into_iter().map(|(step, _)| step).collect(),
+            MovementPattern::Rook { .. } => Connectivity::Four.steps(map, position.0, position.1).into_iter().map(|(step, _)| step).collect(),
+            //穿墙的怪物可以走到任何相邻的格子
+            MovementPattern::Phase => map.neighbours(position.0, position.1, true).collect(),
+        }
+    }
+
+    //穿墙的怪物朝目标走一步：选择离目标最近的相邻格子
+    fn phase_step_towards(&self, map: &Map, position: (usize, usize), target: (usize, usize)) -> Option<(usize, usize)> {
+        if position == target {
+            return None;
+        }
+        let distance = |(x, y): (usize, usize)| x.abs_diff(target.0).pow(2) + y.abs_diff(target.1).pow(2);
+        self.steps(map, position, MovementPattern::Phase).into_iter().min_by_key(|&step| distance(step))
+    }
+
+    //逃跑时的下一步：选择离玩家最远的相邻格子，没有更远的格子时返回 None
+    fn flee_step(&self, map: &Map, position: (usize, usize), player: (usize, usize), movement: MovementPattern) -> Option<(usize, usize)> {
+        let distance = |(x, y): (usize, usize)| x.abs_diff(player.0).pow(2) + y.abs_diff(player.1).pow(2);
+        self.steps(map, position, movement)
+            .into_iter()
+            .filter(|&step| distance(step) > distance(position))
+            .max_by_key(|&step| distance(step))
+    }
+}
+
+//怪物在 from 处能否看见 to 处的玩家：视线没有被墙壁挡住，并且在怪物的视野半径之内
+fn can_see(player_fov: &FieldOfView, from: (usize, usize), to: (usize, usize), sight_radius: f32) -> bool {
+    let dx = from.0 as f32 - to.0 as f32;
+    let dy = from.1 as f32 - to.1 as f32;
+    player_fov.is_visible(from.0, from.1) && (dx * dx + dy * dy).sqrt() <= sight_radius
+}
+
+//从 from 处射向 to 处的方向：两者必须在同一条横线、竖线或斜线上，并且距离不超过 range
+fn firing_direction(from: (usize, usize), to: (usize, usize), range: usize) -> Option<(isize, isize)> {
+    let dx = to.0 as isize - from.0 as isize;
+    let dy = to.1 as isize - from.1 as isize;
+    let aligned = dx == 0 || dy == 0 || dx.abs() == dy.abs();
+    let distance = dx.abs().max(dy.abs()) as usize;
+    if aligned && distance > 0 && distance <= range {
+        Some((dx.signum(), dy.signum()))
+    } else {
+        None
+    }
+}
+
+//第 i 个怪物移动到 (new_x, new_y) 时是否会与其他怪物重叠
+fn overlaps(positions: &[(f32, f32)], new_positions: &[(f32, f32)], i: usize, new_x: f32, new_y: f32) -> bool {
+    positions.iter().enumerate().any(|(j, &(x, y))| {
+        if i == j { return false; }
+        (x - new_x).abs() < 1.0 && (y - new_y).abs() < 1.0
+    }) || new_positions.iter().any(|&(x, y)| {
+        (x - new_x).abs() < 1.0 && (y - new_y).abs() < 1.0
+    })
 }
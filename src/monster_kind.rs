@@ -0,0 +1,77 @@
+//怪物种类模块：登记每一种怪物的速度、伤害、生命值、移动方式、行为参数和图片
+//每种怪物的具体数值写在定义文件（data/definitions.toml）的 [monsters.种类] 中
+use serde::{Deserialize, Serialize};
+use crate::ai::Behaviour;
+
+//怪物的种类
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MonsterKind {
+    //普通的小怪
+    Grunt,
+    //跑得很快，但只能像国际象棋的车一样走直线
+    Runner,
+    //走得很慢，但打人很疼、很耐打
+    Brute,
+    //幽灵，可以穿墙
+    Ghost,
+    //弓箭手，站在远处朝玩家射击
+    Archer,
+    //首领，游戏进行一段时间之后出现，生命值降低时会改变攻击方式
+    Boss,
+}
+
+//所有的怪物种类（定义文件必须给每一种都写上数值）
+pub const ALL_KINDS: [MonsterKind; 6] = [
+    MonsterKind::Grunt,
+    MonsterKind::Runner,
+    MonsterKind::Brute,
+    MonsterKind::Ghost,
+    MonsterKind::Archer,
+    MonsterKind::Boss,
+];
+
+//怪物的移动方式
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub enum MovementPattern {
+    //一次走一格，按照难度规定的方式走（四方向或八方向）
+    Walk,
+    //只能上下左右走直线，一次最多滑行 slide 格
+    Rook { slide: usize },
+    //无视墙壁，直接朝目标飘过去（不会离开地图）
+    Phase,
+}
+
+//一种怪物的数值
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MonsterStats {
+    //图片的路径
+    pub texture: String,
+    //两次移动之间的间隔（秒），越小越快
+    pub move_interval: f32,
+    //碰到玩家时造成的伤害
+    pub damage: i32,
+    //生命值
+    pub health: i32,
+    pub movement: MovementPattern,
+    //行为参数（视野、搜索时间、是否逃跑）
+    pub behaviour: Behaviour,
+    //远程攻击，没有写的怪物只能近身攻击
+    #[serde(default)]
+    pub ranged: Option<RangedAttack>,
+}
+
+//远程攻击的数值：玩家和怪物在同一条直线（横、竖或者斜线）上、距离不超过 range 并且怪物能看见玩家时才会射击
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RangedAttack {
+    //弹丸的伤害
+    pub damage: i32,
+    //两次射击之间的间隔（秒）
+    pub cooldown: f32,
+    //弹丸每秒飞过的格子数
+    pub speed: f32,
+    //射程（格子数）
+    pub range: usize,
+}
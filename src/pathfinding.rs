@@ -0,0 +1,221 @@
+//寻路模块：在格子地图上用 A* 算法寻找最短路径，怪物用它绕过墙壁追赶玩家
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use serde::{Deserialize, Serialize};
+use crate::map::{Map, DIRECTIONS_4, DIRECTIONS_8};
+
+//直着走一格和斜着走一格的代价（斜走约为直走的 √2 倍）
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+//斜着走时如何处理墙角
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum CornerRule {
+    //斜走经过的两个直角格子都必须是地板，不能贴着墙角斜穿
+    NoCutting,
+    //两个直角格子中至少有一个是地板即可，但不能从两堵墙的缝隙中钻过去
+    AllowCutting,
+}
+
+//寻路时允许的移动方式
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Connectivity {
+    //只能上下左右移动
+    Four,
+    //可以往八个方向移动，斜走时按照给定的规则处理墙角
+    Eight(CornerRule),
+}
+
+impl Connectivity {
+    //从 (x, y) 能一步走到的所有格子，以及这一步的代价
+    pub fn steps(self, map: &Map, x: usize, y: usize) -> Vec<((usize, usize), u32)> {
+        let directions: &[(isize, isize)] = match self {
+            Connectivity::Four => &DIRECTIONS_4,
+            Connectivity::Eight(_) => &DIRECTIONS_8,
+        };
+
+        let mut steps = Vec::with_capacity(directions.len());
+        for &(dx, dy) in directions {
+            let Some((nx, ny)) = map.neighbour(x, y, dx, dy) else {
+                continue;
+            };
+            if !map.is_floor(nx, ny) {
+                continue;
+            }
+            if dx == 0 || dy == 0 {
+                steps.push(((nx, ny), STRAIGHT_COST));
+                continue;
+            }
+
+            //斜着走：检查经过的两个直角格子
+            let side_a = map.is_floor(nx, y);
+            let side_b = map.is_floor(x, ny);
+            let allowed = match self {
+                Connectivity::Eight(CornerRule::NoCutting) => side_a && side_b,
+                _ => side_a || side_b,
+            };
+            if allowed {
+                steps.push(((nx, ny), DIAGONAL_COST));
+            }
+        }
+        steps
+    }
+
+    //估计从 a 到 b 的剩余代价（A* 的启发函数，不会高估实际代价）
+    fn heuristic(self, a: (usize, usize), b: (usize, usize)) -> u32 {
+        let dx = a.0.abs_diff(b.0) as u32;
+        let dy = a.1.abs_diff(b.1) as u32;
+        match self {
+            Connectivity::Four => (dx + dy) * STRAIGHT_COST,
+            Connectivity::Eight(_) => {
+                STRAIGHT_COST * dx.max(dy) + (DIAGONAL_COST - STRAIGHT_COST) * dx.min(dy)
+            }
+        }
+    }
+}
+
+//用 A* 算法寻找从 start 到 goal 的最短路径
+//extra_cost 给每个格子附加额外的代价（例如被其他怪物占着的格子），用来实现"软障碍"：能绕开就绕开，绕不开也可以走
+//返回的路径不包括起点、包括终点；走不到时返回 None
+pub fn find_path(
+    map: &Map,
+    start: (usize, usize),
+    goal: (usize, usize),
+    connectivity: Connectivity,
+    extra_cost: impl Fn(usize, usize) -> u32,
+) -> Option<Vec<(usize, usize)>> {
+    if !map.is_floor(goal.0, goal.1) {
+        return None;
+    }
+    if start == goal {
+        return Some(Vec::new());
+    }
+
+    let width = map.width();
+    let index = |(x, y): (usize, usize)| y * width + x;
+    let mut best_cost = vec![u32::MAX; width * map.height()];
+    let mut came_from: Vec<Option<(usize, usize)>> = vec![None; width * map.height()];
+    //优先队列按 (估计总代价, 已走代价) 从小到大取出
+    let mut open = BinaryHeap::new();
+
+    best_cost[index(start)] = 0;
+    open.push(Reverse((connectivity.heuristic(start, goal), 0, start)));
+
+    while let Some(Reverse((_, cost, current))) = open.pop() {
+        if current == goal {
+            //沿着 came_from 倒推出完整路径
+            let mut path = vec![goal];
+            let mut node = goal;
+            while let Some(previous) = came_from[index(node)] {
+                if previous == start {
+                    break;
+                }
+                path.push(previous);
+                node = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        //同一个格子可能被重复放进队列，已经有更好的走法时跳过
+        if cost > best_cost[index(current)] {
+            continue;
+        }
+
+        for (next, step_cost) in connectivity.steps(map, current.0, current.1) {
+            let new_cost = cost + step_cost + extra_cost(next.0, next.1);
+            if new_cost < best_cost[index(next)] {
+                best_cost[index(next)] = new_cost;
+                came_from[index(next)] = Some(current);
+                open.push(Reverse((new_cost + connectivity.heuristic(next, goal), new_cost, next)));
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::Tile;
+
+    //用字符画出地图：'#' 为墙壁，'.' 为地板
+    fn map(rows: &[&str]) -> Map {
+        let mut map = Map::new(rows[0].len(), rows.len(), Tile::Floor);
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == '#' {
+                    map.set(x, y, Tile::Wall);
+                }
+            }
+        }
+        map
+    }
+
+    fn targets(steps: Vec<((usize, usize), u32)>) -> Vec<(usize, usize)> {
+        let mut targets: Vec<_> = steps.into_iter().map(|(pos, _)| pos).collect();
+        targets.sort();
+        targets
+    }
+
+    #[test]
+    fn four_and_eight_connectivity() {
+        let open = map(&["...", "...", "..."]);
+        let four = Connectivity::Four.steps(&open, 1, 1);
+        assert_eq!(targets(four.clone()), vec![(0, 1), (1, 0), (1, 2), (2, 1)]);
+        assert!(four.iter().all(|&(_, cost)| cost == STRAIGHT_COST));
+
+        let eight = Connectivity::Eight(CornerRule::NoCutting).steps(&open, 1, 1);
+        assert_eq!(eight.len(), 8);
+        assert_eq!(eight.iter().filter(|&&(_, cost)| cost == DIAGONAL_COST).count(), 4);
+    }
+
+    #[test]
+    fn corner_rules() {
+        //一边是墙：只有 AllowCutting 可以贴着墙角斜走
+        let corner = map(&[".#", ".."]);
+        assert!(!targets(Connectivity::Eight(CornerRule::NoCutting).steps(&corner, 0, 0)).contains(&(1, 1)));
+        assert!(targets(Connectivity::Eight(CornerRule::AllowCutting).steps(&corner, 0, 0)).contains(&(1, 1)));
+
+        //两边都是墙：哪种规则都不能从缝隙中钻过去
+        let gap = map(&[".#", "#."]);
+        for rule in [CornerRule::NoCutting, CornerRule::AllowCutting] {
+            assert!(Connectivity::Eight(rule).steps(&gap, 0, 0).is_empty());
+            assert_eq!(find_path(&gap, (0, 0), (1, 1), Connectivity::Eight(rule), |_, _| 0), None);
+        }
+    }
+
+    #[test]
+    fn shortest_paths_go_around_walls() {
+        let open = map(&[".....", ".....", ".....", ".....", "....."]);
+        let path = |connectivity| find_path(&open, (0, 0), (4, 4), connectivity, |_, _| 0).unwrap();
+        assert_eq!(path(Connectivity::Four).len(), 8);
+        assert_eq!(path(Connectivity::Eight(CornerRule::NoCutting)), vec![(1, 1), (2, 2), (3, 3), (4, 4)]);
+
+        //中间有一堵墙，只能从右边绕过去
+        let wall = map(&[".....", "####.", "....."]);
+        let path = find_path(&wall, (0, 0), (0, 2), Connectivity::Four, |_, _| 0).unwrap();
+        assert_eq!(path.len(), 10);
+        assert!(path.contains(&(4, 1)));
+        assert_eq!(path.last(), Some(&(0, 2)));
+
+        //终点是墙壁或者被围起来时走不到
+        let closed = map(&["..#.", "..#.", "..#."]);
+        assert_eq!(find_path(&closed, (0, 0), (3, 0), Connectivity::Eight(CornerRule::AllowCutting), |_, _| 0), None);
+        assert_eq!(find_path(&closed, (0, 0), (2, 0), Connectivity::Four, |_, _| 0), None);
+    }
+
+    #[test]
+    fn extra_cost_is_a_soft_obstacle() {
+        //从左到右有上下两条一样长的路
+        let ring = map(&["...", ".#.", "..."]);
+        let path = |extra_cost: &dyn Fn(usize, usize) -> u32| {
+            find_path(&ring, (0, 1), (2, 1), Connectivity::Four, extra_cost).unwrap()
+        };
+        //上面那条路被占着，就从下面绕
+        assert!(path(&|x, y| if (x, y) == (1, 0) { 100 } else { 0 }).contains(&(1, 2)));
+        assert!(path(&|x, y| if (x, y) == (1, 2) { 100 } else { 0 }).contains(&(1, 0)));
+        //两条路都被占着也照样能走过去
+        assert_eq!(path(&|x, _| if x == 1 { 100 } else { 0 }).len(), 4);
+    }
+}
@@ -0,0 +1,177 @@
+//飞行道具模块：远程怪物射出的弹丸沿着格子直线飞行，撞到墙壁就消失，碰到玩家时造成伤害
+use crate::map::Map;
+use serde::{Deserialize, Serialize};
+
+//单个弹丸
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Projectile {
+    //弹丸所在的格子
+    pub x: usize,
+    pub y: usize,
+    //上一步模拟时弹丸所在的格子，渲染时在两者之间插值
+    pub prev_x: usize,
+    pub prev_y: usize,
+    //飞行方向（上下左右或者斜向，每一步走一格）
+    pub direction: (isize, isize),
+    //每秒飞过的格子数
+    pub speed: f32,
+    //碰到玩家时造成的伤害
+    pub damage: i32,
+    //还能再飞多少格
+    pub range_left: usize,
+    //距离飞到下一格的计时器
+    timer: f32,
+}
+
+impl Projectile {
+    //从 (x, y) 处朝 direction 射出一颗弹丸（弹丸从射手所在的格子出发）
+    pub fn new(x: usize, y: usize, direction: (isize, isize), speed: f32, damage: i32, range: usize) -> Self {
+        Self {
+            x,
+            y,
+            prev_x: x,
+            prev_y: y,
+            direction,
+            speed,
+            damage,
+            range_left: range,
+            timer: 0.0,
+        }
+    }
+}
+
+//所有正在飞行的弹丸
+#[derive(Default, Serialize, Deserialize)]
+pub struct Projectiles {
+    pub list: Vec<Projectile>,
+}
+
+impl Projectiles {
+    pub fn spawn(&mut self, projectile: Projectile) {
+        self.list.push(projectile);
+    }
+
+    //让所有弹丸往前飞，撞到墙壁、飞出地图或者飞完射程的弹丸被移除
+    pub fn update(&mut self, delta: f32, map: &Map) {
+        self.list.retain_mut(|projectile| {
+            projectile.prev_x = projectile.x;
+            projectile.prev_y = projectile.y;
+            projectile.timer += delta;
+
+            //一步可能飞过好几格（速度很快或者步长很大时），每一格都要检查有没有撞墙
+            let step_time = 1.0 / projectile.speed;
+            while projectile.timer >= step_time {
+                projectile.timer -= step_time;
+                if projectile.range_left == 0 {
+                    return false;
+                }
+                let (dx, dy) = projectile.direction;
+                match map.neighbour(projectile.x, projectile.y, dx, dy) {
+                    Some((x, y)) if map.is_floor(x, y) => {
+                        projectile.x = x;
+                        projectile.y = y;
+                        projectile.range_left -= 1;
+                    }
+                    _ => return false,
+                }
+            }
+            true
+        });
+    }
+
+    //取出打中玩家的弹丸，返回造成的总伤害
+    //player 为玩家现在的格子，player_prev 为玩家这一步之前的格子：
+    //玩家和弹丸在同一步里互相穿过（交换了位置）也算打中
+    pub fn take_hits(&mut self, player: (usize, usize), player_prev: (usize, usize)) -> i32 {
+        let mut damage = 0;
+        self.list.retain(|projectile| {
+            let position = (projectile.x, projectile.y);
+            let prev = (projectile.prev_x, projectile.prev_y);
+            let hit = position == player || (prev == player && position == player_prev);
+            if hit {
+                damage += projectile.damage;
+            }
+            !hit
+        });
+        damage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //从 (x, 1) 出发朝右飞、每秒一格的弹丸
+    fn bolt(x: usize, damage: i32, range: usize) -> Projectile {
+        Projectile::new(x, 1, (1, 0), 1.0, damage, range)
+    }
+
+    //只有一条横向走廊的地图：地板从 (1, 1) 到 (5, 1)
+    fn corridor() -> Map {
+        Map::walled(7, 3)
+    }
+
+    fn positions(projectiles: &Projectiles) -> Vec<(usize, usize)> {
+        projectiles.list.iter().map(|p| (p.x, p.y)).collect()
+    }
+
+    #[test]
+    fn bolts_stop_at_walls_and_at_their_range() {
+        let map = corridor();
+        let mut projectiles = Projectiles::default();
+        projectiles.spawn(bolt(4, 10, 5));
+        projectiles.spawn(bolt(1, 10, 1));
+
+        projectiles.update(1.0, &map);
+        assert_eq!(positions(&projectiles), [(5, 1), (2, 1)]);
+        //下一格是墙壁，射程也用完了
+        projectiles.update(1.0, &map);
+        assert!(projectiles.list.is_empty());
+
+        //一步飞过好几格时也不会穿墙
+        projectiles.spawn(bolt(1, 10, 10));
+        projectiles.update(10.0, &map);
+        assert!(projectiles.list.is_empty());
+    }
+
+    #[test]
+    fn bolts_hit_the_player_they_reach() {
+        let map = corridor();
+        let mut projectiles = Projectiles::default();
+        projectiles.spawn(bolt(2, 10, 5));
+        projectiles.spawn(bolt(2, 5, 5));
+        projectiles.update(1.0, &map);
+
+        //玩家没动，两颗弹丸都飞到了玩家的格子上
+        assert_eq!(projectiles.take_hits((3, 1), (3, 1)), 15);
+        assert!(projectiles.list.is_empty());
+    }
+
+    #[test]
+    fn swapping_places_with_a_bolt_is_a_hit() {
+        let map = corridor();
+        let mut projectiles = Projectiles::default();
+        projectiles.spawn(bolt(2, 10, 5));
+        projectiles.update(1.0, &map);
+
+        //弹丸从 (2, 1) 飞到 (3, 1)，玩家从 (3, 1) 走到 (2, 1)：两者互相穿过
+        assert_eq!(projectiles.take_hits((2, 1), (3, 1)), 10);
+        assert!(projectiles.list.is_empty());
+    }
+
+    #[test]
+    fn bolts_can_be_dodged() {
+        let map = Map::walled(7, 4);
+        let mut projectiles = Projectiles::default();
+        projectiles.spawn(bolt(2, 10, 5));
+        projectiles.update(1.0, &map);
+
+        //玩家从弹丸要飞到的格子上让开
+        assert_eq!(projectiles.take_hits((3, 2), (3, 1)), 0);
+        //玩家在弹丸前面，朝同一个方向走
+        assert_eq!(projectiles.take_hits((4, 1), (3, 1)), 0);
+        //弹丸刚刚飞过玩家原来的格子，玩家走进了那个格子
+        assert_eq!(projectiles.take_hits((2, 1), (2, 2)), 0);
+        assert_eq!(positions(&projectiles), [(3, 1)]);
+    }
+}
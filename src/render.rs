@@ -0,0 +1,164 @@
+use macroquad::prelude::*;
+use crate::ai::AiState;
+use crate::camera::Camera;
+use crate::game::{Game, SWING_TIME};
+use crate::map::Tile;
+use crate::monster_kind::{MonsterKind, ALL_KINDS};
+use crate::definitions::Definitions;
+use std::collections::HashMap;
+use crate::TILE_SIZE;
+
+//去过但现在看不见的格子画得暗一些
+const EXPLORED_TINT: Color = Color::new(0.35, 0.35, 0.45, 1.0);
+
+//渲染器的结构体
+//作用：持有游戏中用到的所有图片，并负责把模拟层的游戏状态画到屏幕上
+pub struct Renderer {
+    wall_texture: Texture2D, // 墙壁的纹理
+    floor_texture: Texture2D, // 地板的纹理
+    player_texture: Texture2D, // 玩家的纹理
+    monster_textures: HashMap<MonsterKind, Texture2D>, // 每种怪物的纹理
+    health_pack_texture: Texture2D, // 血包的纹理
+}
+
+//加载一张像素风格的图片
+async fn load_pixel_texture(path: &str) -> Texture2D {
+    let texture = load_texture(path).await.unwrap();
+    texture.set_filter(FilterMode::Nearest);
+    texture
+}
+
+//在上一步和这一步的位置之间线性插值
+fn lerp(from: f32, to: f32, alpha: f32) -> f32 {
+    from + (to - from) * alpha
+}
+
+//镜头要对准的位置：玩家（插值之后）中心的像素坐标
+pub fn camera_target(game: &Game, alpha: f32) -> Vec2 {
+    let x = lerp(game.player.prev_x as f32, game.player.x as f32, alpha);
+    let y = lerp(game.player.prev_y as f32, game.player.y as f32, alpha);
+    (vec2(x, y) + 0.5) * TILE_SIZE
+}
+
+impl Renderer {
+    //异步加载所有图片，创建渲染器实例
+    //怪物的图片路径来自定义文件
+    pub async fn new(definitions: &Definitions) -> Self {
+        Self {
+            wall_texture: load_pixel_texture("assets/wall.png").await,
+            floor_texture: load_pixel_texture("assets/floor.png").await,
+            player_texture: load_pixel_texture("assets/player.png").await,
+            monster_textures: {
+                let mut textures = HashMap::new();
+                for kind in ALL_KINDS {
+                    textures.insert(kind, load_pixel_texture(&definitions.monster(kind).texture).await);
+                }
+                textures
+            },
+            health_pack_texture: load_pixel_texture("assets/health_pack.png").await,
+        }
+    }
+
+    //绘制游戏画面的方法
+    //alpha 为当前时刻在上一步和这一步模拟之间的比例，用来让移动更平滑
+    //所有物体都按镜头的位置换算成屏幕坐标，屏幕外的格子、怪物和血包不绘制
+    //战争迷雾：从没见过的格子保持黑色，去过但现在看不见的格子变暗，只有视野中的格子正常显示；
+    //视野之外的怪物和血包不绘制
+    pub fn draw(&self, game: &Game, alpha: f32, camera: &Camera) {
+        let (xs, ys) = camera.visible_tiles(&game.map);
+        for y in ys {
+            for x in xs.clone() {
+                let texture = match game.map.get(x, y) {
+                    Some(Tile::Wall) => &self.wall_texture,
+                    Some(Tile::Floor) => &self.floor_texture,
+                    None => continue,
+                };
+                let tint = if game.fov.is_visible(x, y) {
+                    WHITE
+                } else if game.explored.is_explored(x, y) {
+                    EXPLORED_TINT
+                } else {
+                    continue;
+                };
+
+                let pos = camera.to_screen(vec2(x as f32, y as f32) * TILE_SIZE);
+                draw_texture(texture, pos.x, pos.y, tint);
+            }
+        }
+
+        //即将刷出怪物的出生点上闪烁红色的预警（只在去过的地方显示）
+        let pulse = 0.35 + 0.25 * (get_time() as f32 * 10.0).sin();
+        for &((x, y), _) in &game.waves.pending {
+            if game.explored.is_explored(x, y) && camera.is_visible(x as f32, y as f32) {
+                let pos = camera.to_screen(vec2(x as f32, y as f32) * TILE_SIZE);
+                draw_rectangle(pos.x, pos.y, TILE_SIZE, TILE_SIZE, Color::new(1.0, 0.1, 0.1, pulse));
+                draw_rectangle_lines(pos.x + 2.0, pos.y + 2.0, TILE_SIZE - 4.0, TILE_SIZE - 4.0, 3.0, RED);
+            }
+        }
+
+        //绘制玩家
+        let player_x = lerp(game.player.prev_x as f32, game.player.x as f32, alpha);
+        let player_y = lerp(game.player.prev_y as f32, game.player.y as f32, alpha);
+        self.draw_sprite(&self.player_texture, player_x, player_y, WHITE, camera);
+
+        //攻击时在玩家面前的格子上闪一下
+        if game.player.swing_time > 0.0 {
+            let (dx, dy) = game.player.facing;
+            let pos = camera.to_screen(vec2(player_x + dx as f32, player_y + dy as f32) * TILE_SIZE);
+            let alpha = game.player.swing_time / SWING_TIME;
+            draw_rectangle(pos.x, pos.y, TILE_SIZE, TILE_SIZE, Color::new(1.0, 1.0, 1.0, 0.5 * alpha));
+        }
+
+        //绘制怪物
+        for monster in &game.monsters.list {
+            let (tile_x, tile_y) = monster.tile();
+            if !game.fov.is_visible(tile_x, tile_y) {
+                continue;
+            }
+            let x = lerp(monster.prev_x, monster.x, alpha);
+            let y = lerp(monster.prev_y, monster.y, alpha);
+            //正在逃跑的怪物显示为蓝色
+            let tint = if monster.state == AiState::Flee { Color::new(0.5, 0.7, 1.0, 1.0) } else { WHITE };
+            self.draw_sprite(&self.monster_textures[&monster.kind], x, y, tint, camera);
+            //受过伤的怪物头上显示血条
+            let max_health = monster.stats.health;
+            if monster.health < max_health && camera.is_visible(x, y) {
+                let pos = camera.to_screen(vec2(x, y) * TILE_SIZE);
+                let ratio = monster.health.max(0) as f32 / max_health as f32;
+                draw_rectangle(pos.x + 4.0, pos.y - 8.0, TILE_SIZE - 8.0, 5.0, DARKGRAY);
+                draw_rectangle(pos.x + 4.0, pos.y - 8.0, (TILE_SIZE - 8.0) * ratio, 5.0, RED);
+            }
+        }
+
+        //绘制弹丸
+        for projectile in &game.projectiles.list {
+            if !game.fov.is_visible(projectile.x, projectile.y) {
+                continue;
+            }
+            let x = lerp(projectile.prev_x as f32, projectile.x as f32, alpha);
+            let y = lerp(projectile.prev_y as f32, projectile.y as f32, alpha);
+            if camera.is_visible(x, y) {
+                let center = camera.to_screen((vec2(x, y) + 0.5) * TILE_SIZE);
+                draw_circle(center.x, center.y, TILE_SIZE / 8.0, ORANGE);
+                draw_circle(center.x, center.y, TILE_SIZE / 16.0, YELLOW);
+            }
+        }
+
+        //绘制血包
+        for pack in &game.health_packs.list {
+            if !game.fov.is_visible(pack.x, pack.y) {
+                continue;
+            }
+            self.draw_sprite(&self.health_pack_texture, pack.x as f32, pack.y as f32, WHITE, camera);
+        }
+    }
+
+    //在格子坐标 (x, y) 处绘制一个物体，不在屏幕上时跳过
+    fn draw_sprite(&self, texture: &Texture2D, x: f32, y: f32, tint: Color, camera: &Camera) {
+        if !camera.is_visible(x, y) {
+            return;
+        }
+        let pos = camera.to_screen(vec2(x, y) * TILE_SIZE);
+        draw_texture(texture, pos.x, pos.y, tint);
+    }
+}
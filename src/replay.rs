@@ -0,0 +1,189 @@
+//录像模块：记录一局游戏的种子、难度和每一步模拟的输入，并可以把录像重新喂给 Game::update
+//同样的种子 + 同样的输入序列 => 完全相同的一局游戏，可以用来复现bug和做回归测试
+//游戏规则一改，旧录像的输入就会得到不同的结果，所以录像带有版本号：改了模拟的逻辑之后提高 REPLAY_VERSION，旧录像会被拒绝
+use std::fs;
+use serde::{Deserialize, Serialize};
+use crate::game::{Game, GameEvent, Input, RunStats};
+use crate::menu::GameDifficulty;
+use crate::level::Level;
+use crate::definitions::Definitions;
+
+//录像格式（以及模拟逻辑）的版本号
+pub const REPLAY_VERSION: u32 = 2;
+
+//一整局游戏的录像
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    pub difficulty: GameDifficulty,
+    //这一局使用的关卡（完整保存在录像里，换了关卡文件也能正确播放）
+    pub level: Level,
+    //这一局使用的数值定义（同样完整保存，改了定义文件也能正确播放）
+    pub definitions: Definitions,
+    //录制时每秒的模拟次数，播放时按同样的步长推进
+    pub tick_rate: f32,
+    //每一步模拟的输入快照
+    pub inputs: Vec<Input>,
+}
+
+impl Replay {
+    //开始录制一局新的游戏
+    pub fn new(seed: u64, difficulty: GameDifficulty, level: Level, definitions: Definitions, tick_rate: f32) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            seed,
+            difficulty,
+            level,
+            definitions,
+            tick_rate,
+            inputs: Vec::new(),
+        }
+    }
+
+    //记录一步模拟的输入
+    pub fn record(&mut self, input: Input) {
+        self.inputs.push(input);
+    }
+
+    //把录像保存到文件
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let text = serde_json::to_string(self).map_err(|e| format!("无法序列化录像: {}", e))?;
+        fs::write(path, text).map_err(|e| format!("无法写入录像文件 {}: {}", path, e))
+    }
+
+    //从文件读取录像，版本不一致或者步长不合法时返回错误信息
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("无法读取录像文件 {}: {}", path, e))?;
+        //先只读版本号（没有版本号的是加版本号之前录的，当作版本 0）
+        let header: ReplayHeader =
+            serde_json::from_str(&text).map_err(|e| format!("录像文件 {} 格式错误: {}", path, e))?;
+        if header.version != REPLAY_VERSION {
+            return Err(format!(
+                "录像文件 {} 的版本是 {}，当前版本是 {}，无法播放",
+                path, header.version, REPLAY_VERSION
+            ));
+        }
+        let replay: Self = serde_json::from_str(&text).map_err(|e| format!("录像文件 {} 格式错误: {}", path, e))?;
+        if !(replay.tick_rate.is_finite() && replay.tick_rate > 0.0) {
+            return Err(format!("录像文件 {} 的 tick_rate 必须是正数，实际为 {}", path, replay.tick_rate));
+        }
+        Ok(replay)
+    }
+
+    //根据录像创建对应的游戏实例
+    pub fn new_game(&self) -> Game {
+        Game::new(self.difficulty, self.seed, &self.level, &self.definitions)
+    }
+
+    //不打开窗口，直接把整段录像跑完，返回最终的统计信息
+    //录像在游戏结束前就用完时，返回最后一步时的统计信息
+    pub fn simulate(&self) -> RunStats {
+        let mut game = self.new_game();
+        let step = 1.0 / self.tick_rate;
+        for input in &self.inputs {
+            for event in game.update(input, step) {
+                if let GameEvent::GameOver(stats) = event {
+                    return stats;
+                }
+            }
+        }
+        game.stats()
+    }
+}
+
+//只读取版本号，先确认版本一致再解析整个录像
+#[derive(Deserialize)]
+struct ReplayHeader {
+    #[serde(default)]
+    version: u32,
+}
+
+//录像的播放器：按顺序一步一步地取出录像中的输入
+pub struct ReplayPlayer {
+    replay: Replay,
+    next_tick: usize,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            next_tick: 0,
+        }
+    }
+
+    //取出下一步的输入，录像播放完毕时返回 None
+    pub fn next_input(&mut self) -> Option<Input> {
+        let input = self.replay.inputs.get(self.next_tick).copied();
+        self.next_tick += 1;
+        input
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //构造一段录像：玩家在场地里来回走动，每隔一段时间按一次空格
+    fn sample_replay(seed: u64) -> Replay {
+        let mut replay = Replay::new(seed, GameDifficulty::Hard, Level::default_arena(), Definitions::builtin(), 60.0);
+        for i in 0..6000 {
+            let input = Input {
+                left: i % 120 == 0,
+                right: i % 120 == 60,
+                up: i % 90 == 30,
+                down: i % 90 == 75,
+                pick_up: i % 20 == 0,
+                attack: i % 15 == 0,
+                toggle_pause: false,
+            };
+            replay.record(input);
+        }
+        replay
+    }
+
+    #[test]
+    fn same_replay_gives_same_result() {
+        let replay = sample_replay(42);
+        assert_eq!(replay.simulate(), replay.simulate());
+    }
+
+    #[test]
+    fn replay_survives_save_and_load() {
+        let replay = sample_replay(7);
+        let path = std::env::temp_dir().join("monster_replay_test.json");
+        let path = path.to_str().unwrap();
+        replay.save(path).unwrap();
+        let loaded = Replay::load(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(loaded.simulate(), replay.simulate());
+    }
+
+    //固定的一段录像得到固定的结果：改了模拟的逻辑导致这个测试失败时，要提高 REPLAY_VERSION 并更新这里的数字
+    #[test]
+    fn known_replay_gives_known_result() {
+        let mut replay = sample_replay(7);
+        replay.difficulty = GameDifficulty::Easy;
+        let stats = replay.simulate();
+        assert!((stats.game_time - 14.0166).abs() < 1e-3, "game_time = {}", stats.game_time);
+        assert_eq!((stats.health_packs_collected, stats.monster_attacks), (0, 5));
+    }
+
+    #[test]
+    fn bad_version_and_tick_rate_are_rejected() {
+        let path = std::env::temp_dir().join(format!("monster_replay_bad_{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let mut replay = sample_replay(1);
+        replay.version = REPLAY_VERSION + 1;
+        replay.save(path).unwrap();
+        assert!(Replay::load(path).err().unwrap().contains("版本"));
+
+        replay.version = REPLAY_VERSION;
+        replay.tick_rate = 0.0;
+        replay.save(path).unwrap();
+        assert!(Replay::load(path).err().unwrap().contains("tick_rate"));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,61 @@
+//可以保存进存档的随机数生成器
+//rand 的 SmallRng 不能序列化，这里用 rand_xoshiro 中同样的算法（64 位平台上 SmallRng 就是 Xoshiro256PlusPlus），
+//并且用同样的方式把 u64 种子展开成内部状态，所以同一个种子得到的随机序列和以前完全一样，不需要因此提高录像的版本号
+use ::rand::{RngCore, SeedableRng};
+use rand_xoshiro::Xoshiro256PlusPlus;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GameRng(Xoshiro256PlusPlus);
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), ::rand::Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
+
+//只实现 from_seed，seed_from_u64 使用默认的展开方式（和 SmallRng 一样）
+impl SeedableRng for GameRng {
+    type Seed = [u8; 32];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Self(Xoshiro256PlusPlus::from_seed(seed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::rand::Rng;
+    use ::rand::rngs::SmallRng;
+
+    #[test]
+    fn same_sequence_as_small_rng() {
+        for seed in [0, 1, 42, u64::MAX] {
+            let mut small = SmallRng::seed_from_u64(seed);
+            let mut game = GameRng::seed_from_u64(seed);
+            for _ in 0..100 {
+                assert_eq!(small.gen_range(0..1000), game.gen_range(0..1000));
+                assert_eq!(small.r#gen::<f32>(), game.r#gen::<f32>());
+            }
+        }
+
+        //存档之后读回来，接下来的随机序列不变
+        let mut rng = GameRng::seed_from_u64(7);
+        rng.next_u64();
+        let mut restored: GameRng = serde_json::from_str(&serde_json::to_string(&rng).unwrap()).unwrap();
+        assert_eq!(rng.next_u64(), restored.next_u64());
+    }
+}
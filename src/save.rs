@@ -0,0 +1,219 @@
+//存档模块：暂停时选择 "Save & Quit" 会把整局游戏的状态（地图、玩家、怪物、血包、弹丸、计时器和随机数生成器的状态）写进存档
+//菜单上选择 "Continue" 时读回来，从存档时的那一步接着模拟，和没有退出过完全一样
+//存档文件带有版本号，Game 的结构改变之后提高 SAVE_VERSION，旧版本的存档会被拒绝，而不是读出错乱的数据
+use std::fs;
+use std::io::ErrorKind;
+use serde::{Deserialize, Serialize};
+use crate::date::Date;
+use crate::game::Game;
+
+//存档文件
+pub const SAVE_PATH: &str = "data/savegame.json";
+//存档格式的版本号
+pub const SAVE_VERSION: u32 = 2;
+
+//一局进行中的游戏的存档
+#[derive(Serialize, Deserialize)]
+pub struct SaveFile {
+    pub version: u32,
+    //存档时每秒的模拟次数，继续游戏时按同样的步长推进
+    pub tick_rate: f32,
+    //正在玩的每日挑战的日期，不是每日挑战时为 None
+    pub daily: Option<Date>,
+    pub game: Game,
+}
+
+//写存档时借用正在进行的游戏，不需要复制整个 Game
+#[derive(Serialize)]
+struct SaveFileRef<'a> {
+    version: u32,
+    tick_rate: f32,
+    daily: Option<Date>,
+    game: &'a Game,
+}
+
+//只读取版本号，先确认版本兼容再解析整个存档
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+//把正在进行的游戏写进存档
+pub fn save(path: &str, game: &Game, tick_rate: f32, daily: Option<Date>) -> Result<(), String> {
+    let save = SaveFileRef {
+        version: SAVE_VERSION,
+        tick_rate,
+        daily,
+        game,
+    };
+    let text = serde_json::to_string(&save).map_err(|e| format!("无法序列化存档: {}", e))?;
+    fs::write(path, text).map_err(|e| format!("无法写入存档 {}: {}", path, e))
+}
+
+//读取存档，没有存档时返回 None；版本不兼容或者格式错误时返回错误信息
+pub fn load(path: &str) -> Result<Option<SaveFile>, String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("无法读取存档 {}: {}", path, e)),
+    };
+    let header: SaveHeader =
+        serde_json::from_str(&text).map_err(|e| format!("存档 {} 格式错误: {}", path, e))?;
+    if header.version != SAVE_VERSION {
+        return Err(format!(
+            "存档 {} 的版本是 {}，当前版本是 {}，无法继续这一局",
+            path, header.version, SAVE_VERSION
+        ));
+    }
+    let save: SaveFile = serde_json::from_str(&text).map_err(|e| format!("存档 {} 格式错误: {}", path, e))?;
+    if !(save.tick_rate.is_finite() && save.tick_rate > 0.0) {
+        return Err(format!("存档 {} 的 tick_rate 必须是正数，实际为 {}", path, save.tick_rate));
+    }
+    check(&save.game).map_err(|e| format!("存档 {} 有误: {}", path, e))?;
+    Ok(Some(save))
+}
+
+//检查存档里的游戏前后对得上：视野和地图一样大，玩家、怪物、血包、弹丸和各种出生点都在地图之内
+//（怪物可能穿墙，弹丸从射手的格子出发，所以它们只要求在地图上，其他的都要求在地板上）
+fn check(game: &Game) -> Result<(), String> {
+    let map = &game.map;
+    if !game.fov.fits(map) || !game.explored.fits(map) {
+        return Err("视野的大小和地图不一样".to_string());
+    }
+    let on_map = |(x, y): (usize, usize)| map.get(x, y).is_some();
+    let player = &game.player;
+    if !map.is_floor(player.x, player.y) || !map.is_floor(player.prev_x, player.prev_y) {
+        return Err(format!("玩家的位置 ({}, {}) 不在地图的地板上", player.x, player.y));
+    }
+    if let Some(monster) = game.monsters.list.iter().find(|m| !on_map(m.tile())) {
+        return Err(format!("怪物的位置 ({}, {}) 不在地图上", monster.x, monster.y));
+    }
+    if let Some(shot) = game
+        .projectiles
+        .list
+        .iter()
+        .find(|p| !on_map((p.x, p.y)) || !on_map((p.prev_x, p.prev_y)))
+    {
+        return Err(format!("弹丸的位置 ({}, {}) 不在地图上", shot.x, shot.y));
+    }
+    if !game.health_packs.fits(map) {
+        return Err("血包或者血包的刷新区域不在地图的地板上".to_string());
+    }
+    if !game.waves.fits(map) || !game.boss.fits(map) {
+        return Err("怪物的出生点不在地图的地板上".to_string());
+    }
+    Ok(())
+}
+
+//是否有存档
+pub fn exists(path: &str) -> bool {
+    fs::metadata(path).is_ok()
+}
+
+//删除存档（继续游戏之后存档就用掉了，同一局不能读两次）
+pub fn delete(path: &str) {
+    if let Err(e) = fs::remove_file(path)
+        && e.kind() != ErrorKind::NotFound
+    {
+        eprintln!("无法删除存档 {}: {}", path, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definitions::Definitions;
+    use crate::dungeon::{self, DungeonStyle};
+    use crate::game::Input;
+    use crate::menu::GameDifficulty;
+
+    fn temp_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("{}_{}.json", name, std::process::id()));
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn loaded_game_continues_exactly() {
+        let level = dungeon::generate(DungeonStyle::Rooms, 7);
+        let mut game = Game::new(GameDifficulty::Hard, 7, &level, &Definitions::builtin());
+        let inputs = [
+            Input { right: true, ..Input::default() },
+            Input { down: true, attack: true, ..Input::default() },
+            Input::default(),
+        ];
+        for i in 0..300 {
+            game.update(&inputs[i % inputs.len()], 1.0 / 60.0);
+        }
+
+        let path = temp_path("savegame");
+        save(&path, &game, 60.0, None).unwrap();
+        let mut loaded = load(&path).unwrap().unwrap().game;
+        delete(&path);
+        assert!(!exists(&path));
+
+        //读回来的游戏和原来的游戏接下来的每一步都一样（包括随机数）；比较 Value 而不是文本，不受 HashMap 顺序的影响
+        for i in 0..600 {
+            game.update(&inputs[i % inputs.len()], 1.0 / 60.0);
+            loaded.update(&inputs[i % inputs.len()], 1.0 / 60.0);
+        }
+        assert_eq!(serde_json::to_value(&game).unwrap(), serde_json::to_value(&loaded).unwrap());
+    }
+
+    #[test]
+    fn inconsistent_save_is_rejected() {
+        let game = Game::new(GameDifficulty::Easy, 1, &crate::level::Level::default_arena(), &Definitions::builtin());
+        let path = temp_path("savegame_bad");
+        save(&path, &game, 60.0, None).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&text).unwrap();
+
+        //地图的格子被截掉了一半
+        let mut truncated = json.clone();
+        truncated["game"]["map"]["tiles"].as_array_mut().unwrap().truncate(10);
+        fs::write(&path, truncated.to_string()).unwrap();
+        assert!(load(&path).err().unwrap().contains("地图应有"));
+
+        //宽乘以高会溢出
+        let mut overflow = json.clone();
+        overflow["game"]["map"]["width"] = serde_json::json!(usize::MAX);
+        fs::write(&path, overflow.to_string()).unwrap();
+        assert!(load(&path).err().unwrap().contains("地图应有"));
+
+        //把某个字段改成 value 之后，读取存档的错误信息
+        let error_with = |pointer: &str, value: serde_json::Value| {
+            let mut changed = json.clone();
+            *changed.pointer_mut(pointer).unwrap() = value;
+            fs::write(&path, changed.to_string()).unwrap();
+            load(&path).err().unwrap()
+        };
+        assert!(error_with("/game/player/x", serde_json::json!(1000)).contains("玩家的位置"));
+        assert!(error_with("/game/health_packs/spawn_tiles", serde_json::json!([])).contains("血包"));
+        assert!(error_with("/game/health_packs/spawn_tiles/0", serde_json::json!([0, 0])).contains("血包"));
+        assert!(error_with("/game/waves/spawn_tiles/0", serde_json::json!([1000, 1])).contains("出生点"));
+        assert!(error_with("/game/boss/spawn_tiles", serde_json::json!([])).contains("出生点"));
+        let shot = serde_json::to_value(crate::projectile::Projectile::new(1000, 1, (1, 0), 8.0, 10, 5)).unwrap();
+        assert!(error_with("/game/projectiles/list", serde_json::json!([shot])).contains("弹丸"));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn bad_tick_rate_is_rejected() {
+        let game = Game::new(GameDifficulty::Easy, 1, &crate::level::Level::default_arena(), &Definitions::builtin());
+        let path = temp_path("savegame_tick_rate");
+        for tick_rate in [0.0, -60.0, f32::NAN] {
+            save(&path, &game, tick_rate, None).unwrap();
+            assert!(load(&path).err().unwrap().contains("tick_rate"), "tick_rate = {}", tick_rate);
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn incompatible_version_is_rejected() {
+        let path = temp_path("savegame_old");
+        fs::write(&path, format!("{{\"version\": {}, \"game\": {{}}}}", SAVE_VERSION + 1)).unwrap();
+        let error = load(&path).err().unwrap();
+        assert!(error.contains("版本"));
+        fs::remove_file(&path).unwrap();
+        assert!(load(&path).unwrap().is_none());
+    }
+}
@@ -0,0 +1,115 @@
+//计分模块：把存活时间、吃到的血包、躲开的伤害和击杀数换算成分数，再乘以难度的倍率
+//各项的分值和各个难度的倍率写在定义文件的 [scoring] 里，不同难度的分数因此可以放在一起比较
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::menu::GameDifficulty;
+
+//计分的规则
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScoringDef {
+    //每存活一秒的分数
+    pub points_per_second: f32,
+    //每吃到一个血包的分数
+    pub points_per_pack: u32,
+    //每打死一个怪物的分数
+    pub points_per_kill: u32,
+    //每分钟"允许"被攻击的次数，实际被攻击的次数比它少多少，就算躲开了多少次
+    pub par_hits_per_minute: f32,
+    //每躲开一次攻击的分数
+    pub points_per_avoided_hit: u32,
+    //各个难度的倍率
+    pub multipliers: HashMap<GameDifficulty, f32>,
+}
+
+//一局的分数和各项的明细
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ScoreBreakdown {
+    pub survival: u32,
+    pub packs: u32,
+    pub avoided_hits: u32,
+    pub avoided: u32,
+    pub kills: u32,
+    //难度的倍率
+    pub multiplier: f32,
+    //各项相加再乘以倍率之后的总分
+    pub total: u32,
+}
+
+impl ScoreBreakdown {
+    //根据一局的数据计算分数
+    pub fn new(
+        rules: &ScoringDef,
+        difficulty: GameDifficulty,
+        game_time: f32,
+        health_packs_collected: usize,
+        monster_attacks: usize,
+        kills: usize,
+    ) -> Self {
+        let minutes = game_time / 60.0;
+        let avoided_hits = (rules.par_hits_per_minute * minutes - monster_attacks as f32).max(0.0) as u32;
+
+        let survival = (game_time * rules.points_per_second) as u32;
+        let packs = health_packs_collected as u32 * rules.points_per_pack;
+        let avoided = avoided_hits * rules.points_per_avoided_hit;
+        let kills = kills as u32 * rules.points_per_kill;
+        //检查定义时已经保证每个难度都有倍率
+        let multiplier = rules.multipliers.get(&difficulty).copied().unwrap_or(1.0);
+        let total = ((survival + packs + avoided + kills) as f32 * multiplier).round() as u32;
+        Self {
+            survival,
+            packs,
+            avoided_hits,
+            avoided,
+            kills,
+            multiplier,
+            total,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::definitions::Definitions;
+
+    fn rules() -> ScoringDef {
+        ScoringDef {
+            points_per_second: 10.0,
+            points_per_pack: 50,
+            points_per_kill: 100,
+            par_hits_per_minute: 6.0,
+            points_per_avoided_hit: 40,
+            multipliers: HashMap::from([(GameDifficulty::Easy, 1.0), (GameDifficulty::Hard, 2.0)]),
+        }
+    }
+
+    #[test]
+    fn components_add_up_and_are_multiplied() {
+        //两分钟允许被攻击 12 次，实际 4 次，躲开了 8 次
+        let score = ScoreBreakdown::new(&rules(), GameDifficulty::Hard, 120.0, 3, 4, 5);
+        assert_eq!(score.survival, 1200);
+        assert_eq!(score.packs, 150);
+        assert_eq!(score.avoided_hits, 8);
+        assert_eq!(score.avoided, 320);
+        assert_eq!(score.kills, 500);
+        assert_eq!(score.total, (1200 + 150 + 320 + 500) * 2);
+    }
+
+    #[test]
+    fn being_hit_a_lot_never_costs_points() {
+        let score = ScoreBreakdown::new(&rules(), GameDifficulty::Easy, 30.0, 0, 50, 0);
+        assert_eq!(score.avoided, 0);
+        assert_eq!(score.total, 300);
+    }
+
+    #[test]
+    fn harder_difficulty_ranks_the_same_run_higher() {
+        let definitions = Definitions::builtin();
+        let score = |difficulty| ScoreBreakdown::new(&definitions.scoring, difficulty, 90.0, 2, 3, 4).total;
+        assert!(score(GameDifficulty::Hard) > score(GameDifficulty::Medium));
+        assert!(score(GameDifficulty::Medium) > score(GameDifficulty::Easy));
+        //自定义难度的数值可以随便调，分数打折
+        assert!(score(GameDifficulty::Custom) < score(GameDifficulty::Easy));
+    }
+}
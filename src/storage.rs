@@ -0,0 +1,84 @@
+//存储模块：排行榜、成就、每日挑战记录和自定义难度的预设都是 data/ 下的 JSON 文件，读写的方式都一样
+//文件不存在时当作空的记录；文件内容损坏时打印错误信息，把它改名为 .corrupt 留着（不覆盖以前留下的），再从空的记录开始，以后照样可以保存
+use std::fs;
+use std::io::ErrorKind;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+//从 JSON 文件读取，文件不存在或者出错时返回默认值；what 为错误信息中文件的名称，例如 "排行榜文件"
+//只有内容损坏时才把文件改名留着；读不了文件（例如没有权限）时不动它，以免把好好的记录挪走
+pub fn load_json_or_recover<T: DeserializeOwned + Default>(path: &str, what: &str) -> T {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == ErrorKind::NotFound => return T::default(),
+        Err(e) => {
+            eprintln!("无法读取{} {}: {}", what, path, e);
+            return T::default();
+        }
+    };
+    match serde_json::from_str(&text) {
+        Ok(value) => value,
+        Err(e) => {
+            eprintln!("{} {} 格式错误: {}", what, path, e);
+            let backup = backup_path(path);
+            match fs::rename(path, &backup) {
+                Ok(()) => eprintln!("已把损坏的{}改名为 {}", what, backup),
+                Err(e) => eprintln!("无法把损坏的{}改名为 {}: {}", what, backup, e),
+            }
+            T::default()
+        }
+    }
+}
+
+//损坏的文件改成的名字：path.corrupt 已经存在时依次尝试 path.corrupt.1、path.corrupt.2 ……，不覆盖以前留下的文件
+fn backup_path(path: &str) -> String {
+    let mut backup = format!("{}.corrupt", path);
+    let mut n = 0;
+    while fs::metadata(&backup).is_ok() {
+        n += 1;
+        backup = format!("{}.corrupt.{}", path, n);
+    }
+    backup
+}
+
+//保存到 JSON 文件
+pub fn save_json<T: Serialize>(path: &str, value: &T, what: &str) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(value).map_err(|e| format!("无法序列化{}: {}", what, e))?;
+    fs::write(path, text).map_err(|e| format!("无法写入{} {}: {}", what, path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> String {
+        let path = std::env::temp_dir().join(format!("{}_{}.json", name, std::process::id()));
+        path.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn earlier_backups_are_kept() {
+        let path = temp_path("storage_backups");
+        for text in ["first", "second"] {
+            fs::write(&path, text).unwrap();
+            let value: Vec<u32> = load_json_or_recover(&path, "测试文件");
+            assert!(value.is_empty());
+        }
+        assert_eq!(fs::read_to_string(format!("{}.corrupt", path)).unwrap(), "first");
+        assert_eq!(fs::read_to_string(format!("{}.corrupt.1", path)).unwrap(), "second");
+        fs::remove_file(format!("{}.corrupt", path)).unwrap();
+        fs::remove_file(format!("{}.corrupt.1", path)).unwrap();
+    }
+
+    #[test]
+    fn unreadable_file_is_left_alone() {
+        //路径是一个目录：读取失败，但不是内容损坏
+        let path = temp_path("storage_unreadable");
+        fs::create_dir_all(&path).unwrap();
+        let value: Vec<u32> = load_json_or_recover(&path, "测试文件");
+        assert!(value.is_empty());
+        assert!(fs::metadata(&path).unwrap().is_dir());
+        assert!(fs::metadata(format!("{}.corrupt", path)).is_err());
+        fs::remove_dir(&path).unwrap();
+    }
+}
@@ -0,0 +1,53 @@
+//固定步长的时钟：把每一帧不固定的帧间隔累积起来，按固定的时间步长推进游戏逻辑
+//这样无论电脑快慢、画面是否卡顿，游戏规则的运行结果都完全一样（录像也能准确重现）
+
+//默认每秒模拟 60 次
+pub const DEFAULT_TICK_RATE: f32 = 60.0;
+//一帧最多补算的时间，防止卡顿很久之后一次性模拟过多步数（"死亡螺旋"）
+const MAX_FRAME_TIME: f32 = 0.25;
+
+pub struct FixedTimestep {
+    //每秒的模拟次数
+    tick_rate: f32,
+    //每一步的时间长度（秒）
+    step: f32,
+    //还没有被模拟掉的累积时间
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    //tick_rate 为每秒的模拟次数
+    pub fn new(tick_rate: f32) -> Self {
+        Self {
+            tick_rate,
+            step: 1.0 / tick_rate,
+            accumulator: 0.0,
+        }
+    }
+
+    //每秒的模拟次数（存档时记下来，继续游戏时按同样的步长推进）
+    pub fn tick_rate(&self) -> f32 {
+        self.tick_rate
+    }
+
+    //每一步的时间长度（秒）
+    pub fn step(&self) -> f32 {
+        self.step
+    }
+
+    //累积这一帧的时间，返回这一帧需要模拟的步数
+    pub fn advance(&mut self, frame_time: f32) -> usize {
+        self.accumulator += frame_time.min(MAX_FRAME_TIME);
+        let mut ticks = 0;
+        while self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            ticks += 1;
+        }
+        ticks
+    }
+
+    //当前时刻位于上一步和下一步之间的比例（0~1），用于渲染时插值
+    pub fn alpha(&self) -> f32 {
+        self.accumulator / self.step
+    }
+}
@@ -0,0 +1,217 @@
+//怪物波次模块：开局的怪物算第 1 波，之后每隔一段时间在怪物出生点上刷出新的一波怪物
+//每一波的间隔越来越短、数量越来越多（具体数值由难度预设决定），刷怪之前会先在出生点上预警一小段时间
+use ::rand::Rng;
+use crate::rng::GameRng;
+use crate::definitions::{Definitions, WaveDef};
+use crate::level::Level;
+use crate::map::Map;
+use crate::monster::Monsters;
+use crate::monster_kind::MonsterKind;
+use serde::{Deserialize, Serialize};
+
+//波次这一步发生的事情
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WaveEvent {
+    //第 wave 波即将出现，开始预警
+    Incoming(usize),
+    //第 wave 波的怪物已经刷出来了
+    Spawned(usize),
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct WaveDirector {
+    //当前的波次（开局为 1）
+    pub wave: usize,
+    //刷怪的压力（动态难度会调整它）：压力越大，波次之间的间隔越短、每一波的怪物越多
+    pub pressure: f32,
+    //正在预警、即将刷出来的怪物：位置和种类
+    pub pending: Vec<((usize, usize), MonsterKind)>,
+    //是否正在预警
+    incoming: bool,
+    //距离下一波开始预警的时间（秒）
+    timer: f32,
+    //预警剩余的时间（秒）
+    telegraph_timer: f32,
+    //可以刷怪的格子（关卡的怪物出生点，没有时为所有地板）
+    spawn_tiles: Vec<(usize, usize)>,
+    config: WaveDef,
+}
+
+impl WaveDirector {
+    pub fn new(level: &Level, config: &WaveDef) -> Self {
+        let spawn_tiles = if level.monster_spawns.is_empty() {
+            level.map.floor_tiles()
+        } else {
+            level.monster_spawns.clone()
+        };
+        Self {
+            wave: 1,
+            pressure: 1.0,
+            pending: Vec::new(),
+            incoming: false,
+            timer: config.interval - config.telegraph_time,
+            telegraph_timer: 0.0,
+            spawn_tiles,
+            config: config.clone(),
+        }
+    }
+
+    //出生点和正在预警的怪物是否都在这张地图的地板上（至少要有一个出生点）
+    pub fn fits(&self, map: &Map) -> bool {
+        !self.spawn_tiles.is_empty()
+            && self.spawn_tiles.iter().all(|&(x, y)| map.is_floor(x, y))
+            && self.pending.iter().all(|&((x, y), _)| map.is_floor(x, y))
+    }
+
+    //已经真正刷出来过的最高波次（正在预警的那一波还不算）
+    pub fn reached(&self) -> usize {
+        if self.incoming { self.wave - 1 } else { self.wave }
+    }
+
+    //第 wave 波与上一波之间的间隔：每一波都乘以 shrink，但不会短于 min_interval（再除以压力）
+    fn interval(&self, wave: usize) -> f32 {
+        let shrunk = self.config.interval * self.config.shrink.powi(wave as i32 - 2);
+        //间隔不能短于预警的时间
+        (shrunk.max(self.config.min_interval) / self.pressure).max(self.config.telegraph_time)
+    }
+
+    //第 wave 波的怪物数量（乘以压力，至少 1 个）
+    fn size(&self, wave: usize) -> usize {
+        let size = self.config.size as f32 + self.config.growth * (wave - 2) as f32;
+        ((size * self.pressure) as usize).max(1)
+    }
+
+    //推进波次的计时：时间到了先选好出生点开始预警，预警结束后把怪物刷出来
+    //场上的怪物已经达到上限时，这一波少刷或者不刷
+    pub fn update(
+        &mut self,
+        delta: f32,
+        definitions: &Definitions,
+        monsters: &mut Monsters,
+        player: (usize, usize),
+        rng: &mut GameRng,
+    ) -> Option<WaveEvent> {
+        if self.incoming {
+            self.telegraph_timer -= delta;
+            if self.telegraph_timer > 0.0 {
+                return None;
+            }
+            self.incoming = false;
+            for (position, kind) in std::mem::take(&mut self.pending) {
+                //预警期间玩家或者其他怪物站到了出生点上，这个怪物就不刷了
+                if position != player && !monsters.is_occupied(position.0, position.1) {
+                    monsters.spawn(kind, definitions.monster(kind).clone(), position);
+                }
+            }
+            return Some(WaveEvent::Spawned(self.wave));
+        }
+
+        self.timer -= delta;
+        if self.timer > 0.0 {
+            return None;
+        }
+
+        //下一波的计时从这一波刷出来之后开始，所以要扣掉预警的时间
+        self.wave += 1;
+        self.timer = self.interval(self.wave + 1) - self.config.telegraph_time;
+        self.incoming = true;
+        self.telegraph_timer = self.config.telegraph_time;
+
+        let room = self.config.max_alive.saturating_sub(monsters.list.len());
+        let count = self.size(self.wave).min(room);
+        let mut candidates: Vec<(usize, usize)> = self
+            .spawn_tiles
+            .iter()
+            .copied()
+            .filter(|&(x, y)| x.abs_diff(player.0).max(y.abs_diff(player.1)) > 2 && !monsters.is_occupied(x, y))
+            .collect();
+        while self.pending.len() < count && !candidates.is_empty() {
+            let position = candidates.swap_remove(rng.gen_range(0..candidates.len()));
+            let kind = self.config.kinds[rng.gen_range(0..self.config.kinds.len())];
+            self.pending.push((position, kind));
+        }
+        Some(WaveEvent::Incoming(self.wave))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::rand::SeedableRng;
+    use crate::menu::GameDifficulty;
+
+    const STEP: f32 = 1.0 / 60.0;
+
+    //默认场地上的简单难度：开局时场上没有怪物
+    fn setup() -> (Definitions, WaveDirector, Monsters, GameRng) {
+        let definitions = Definitions::builtin();
+        let level = Level::default_arena();
+        let preset = definitions.difficulty(GameDifficulty::Easy);
+        let mut rng = GameRng::seed_from_u64(1);
+        let mut monsters = Monsters::new(preset, &definitions, &level, &mut rng);
+        monsters.list.clear();
+        let waves = WaveDirector::new(&level, &preset.waves);
+        (definitions, waves, monsters, rng)
+    }
+
+    //一直推进到有事件发生，返回事件和经过的时间
+    fn next_event(
+        waves: &mut WaveDirector,
+        definitions: &Definitions,
+        monsters: &mut Monsters,
+        rng: &mut GameRng,
+    ) -> (WaveEvent, f32) {
+        let mut elapsed = 0.0;
+        loop {
+            elapsed += STEP;
+            if let Some(event) = waves.update(STEP, definitions, monsters, (3, 3), rng) {
+                return (event, elapsed);
+            }
+        }
+    }
+
+    #[test]
+    fn waves_are_telegraphed_then_spawned() {
+        let (definitions, mut waves, mut monsters, mut rng) = setup();
+        let config = definitions.difficulty(GameDifficulty::Easy).waves.clone();
+
+        let (event, elapsed) = next_event(&mut waves, &definitions, &mut monsters, &mut rng);
+        assert_eq!(event, WaveEvent::Incoming(2));
+        assert!((elapsed - (config.interval - config.telegraph_time)).abs() < 0.1, "elapsed = {}", elapsed);
+        //预警期间这一波还不算到达
+        assert_eq!(waves.reached(), 1);
+        assert!(monsters.list.is_empty());
+        //出生点离玩家至少 3 格
+        let pending: Vec<(usize, usize)> = waves.pending.iter().map(|&(position, _)| position).collect();
+        assert_eq!(pending.len(), config.size);
+        assert!(pending.iter().all(|&(x, y)| x.abs_diff(3).max(y.abs_diff(3)) > 2));
+
+        let (event, elapsed) = next_event(&mut waves, &definitions, &mut monsters, &mut rng);
+        assert_eq!(event, WaveEvent::Spawned(2));
+        assert!((elapsed - config.telegraph_time).abs() < 0.1, "elapsed = {}", elapsed);
+        assert_eq!(waves.reached(), 2);
+        assert_eq!(monsters.list.iter().map(|m| m.tile()).collect::<Vec<_>>(), pending);
+
+        //下一波的间隔缩短了
+        let (event, elapsed) = next_event(&mut waves, &definitions, &mut monsters, &mut rng);
+        assert_eq!(event, WaveEvent::Incoming(3));
+        let expected = config.interval * config.shrink - config.telegraph_time;
+        assert!((elapsed - expected).abs() < 0.1, "elapsed = {}", elapsed);
+    }
+
+    #[test]
+    fn full_arena_gets_no_new_monsters() {
+        let (definitions, mut waves, mut monsters, mut rng) = setup();
+        let max_alive = definitions.difficulty(GameDifficulty::Easy).waves.max_alive;
+        for x in 1..=max_alive {
+            monsters.spawn(MonsterKind::Grunt, definitions.monster(MonsterKind::Grunt).clone(), (x, 8));
+        }
+
+        let (event, _) = next_event(&mut waves, &definitions, &mut monsters, &mut rng);
+        assert_eq!(event, WaveEvent::Incoming(2));
+        assert!(waves.pending.is_empty());
+        let (event, _) = next_event(&mut waves, &definitions, &mut monsters, &mut rng);
+        assert_eq!(event, WaveEvent::Spawned(2));
+        assert_eq!(monsters.list.len(), max_alive);
+    }
+}